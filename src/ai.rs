@@ -0,0 +1,464 @@
+use crate::{chess::*, deck::DeckConfig};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+const INFINITY: i32 = 1_000_000;
+const MATE: i32 = 100_000;
+const MAX_EVENTS: usize = 8;
+const DRAW_MARGIN: i32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    fn max_depth(self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 64,
+        }
+    }
+
+    pub fn time(self) -> Duration {
+        match self {
+            Difficulty::Easy => Duration::from_millis(200),
+            Difficulty::Normal => Duration::from_secs(1),
+            Difficulty::Hard => Duration::from_secs(3),
+        }
+    }
+
+    fn card_plies(self) -> i32 {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 4,
+        }
+    }
+
+    fn noise(self) -> i32 {
+        match self {
+            Difficulty::Easy => 80,
+            Difficulty::Normal => 20,
+            Difficulty::Hard => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    pub deck: DeckConfig,
+    pub choose_cards: bool,
+    pub choose_events: bool,
+}
+
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+fn value(typ: PieceType) -> i32 {
+    match typ {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+fn table(typ: PieceType) -> &'static [i32; 64] {
+    match typ {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => &KING_TABLE,
+    }
+}
+
+pub fn evaluate(board: &Board) -> i32 {
+    let mut score = 0;
+    for color in [Color::White, Color::Black] {
+        let sign = if color == board.active { 1 } else { -1 };
+        for (loc, piece) in board.iter_pieces_of(color) {
+            let rank = if color.is_white() {
+                7 - loc.rank()
+            } else {
+                loc.rank()
+            };
+            let sq = (rank * 8 + loc.file()) as usize;
+            score += sign * (value(piece.typ()) + table(piece.typ())[sq]);
+        }
+    }
+    score
+}
+
+fn without_card(board: &Board, index: usize) -> Board {
+    let mut board = board.clone();
+    let cards = if board.active.is_white() {
+        &mut board.white_cards
+    } else {
+        &mut board.black_cards
+    };
+    cards.remove(index);
+    board
+}
+
+fn order(moves: &mut [Move]) {
+    moves.sort_by_cached_key(|mv| {
+        let victim = mv.captured().map_or(0, |piece| value(piece.typ()));
+        let promotion = mv.promote_to().map_or(0, value);
+        let attacker = value(mv.piece().typ()) / 100;
+        if victim == 0 && promotion == 0 {
+            0
+        } else {
+            -(victim * 10 + promotion * 10 - attacker)
+        }
+    });
+}
+
+struct Search<'a> {
+    rules: &'a Rules,
+    card_plies: i32,
+    deadline: Option<Instant>,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl<'a> Search<'a> {
+    fn new(rules: &'a Rules, card_plies: i32, limit: Option<Duration>) -> Self {
+        Self {
+            rules,
+            card_plies,
+            deadline: limit.map(|limit| Instant::now() + limit),
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    fn timed_out(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes % 1024 == 0 && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self, board: &Board, depth: u32, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        if self.timed_out() {
+            return 0;
+        }
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.get_check().is_some() {
+                -MATE + ply
+            } else {
+                0
+            };
+        }
+        if board.halfmove_clock >= 100 {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiesce(board, alpha, beta);
+        }
+        order(&mut moves);
+        for mv in &moves {
+            let score = -self.after_move(&board.piece_moved(mv), depth - 1, -beta, -alpha, ply + 1);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // Once a move is made the side to move plays a card, so near the root the position is a
+    // chance node over the card and its event rather than a plain min/max node. Cards are
+    // played from the end of the hand and refills land in front of it, so once the search has
+    // used up the known cards the next one is a fresh draw from the deck.
+    fn after_move(&mut self, board: &Board, depth: u32, alpha: i32, beta: i32, ply: i32) -> i32 {
+        if ply > self.card_plies || board.legal_moves().is_empty() {
+            return self.negamax(board, depth, alpha, beta, ply);
+        }
+        let hand = board.hand(board.active);
+        if self.rules.choose_cards && !hand.is_empty() {
+            let mut best = self.negamax(board, depth, -INFINITY, INFINITY, ply);
+            for (i, card) in hand.iter().enumerate() {
+                if hand[..i].contains(card) {
+                    continue;
+                }
+                let value = self.play(&without_card(board, i), *card, depth, ply);
+                best = best.max(value);
+            }
+            return best;
+        }
+        if let Some(card) = hand.last() {
+            return self.play(&without_card(board, hand.len() - 1), *card, depth, ply);
+        }
+        let weights = &self.rules.deck.weights;
+        let total: i64 = weights.values().map(|w| *w as i64).sum();
+        let mut sum = 0;
+        for (card, weight) in weights.iter().filter(|(_, w)| **w > 0) {
+            sum += *weight as i64 * self.play(board, *card, depth, ply) as i64;
+        }
+        (sum / total.max(1)) as i32
+    }
+
+    fn play(&mut self, board: &Board, card: Card, depth: u32, ply: i32) -> i32 {
+        let cands = board.event_candidates(card);
+        if cands.is_empty() {
+            return self.negamax(board, depth, -INFINITY, INFINITY, ply);
+        }
+        let step = cands.len().div_ceil(MAX_EVENTS);
+        let values: Vec<_> = cands
+            .iter()
+            .step_by(step)
+            .map(|ev| self.negamax(&board.event_applied(*ev), depth, -INFINITY, INFINITY, ply))
+            .collect();
+        if self.rules.choose_events {
+            values.into_iter().max().unwrap()
+        } else {
+            values.iter().sum::<i32>() / values.len() as i32
+        }
+    }
+
+    fn assess(&mut self, board: &Board) -> i32 {
+        self.negamax(board, 1, -INFINITY, INFINITY, 0)
+    }
+
+    fn quiesce(&mut self, board: &Board, mut alpha: i32, beta: i32) -> i32 {
+        if self.timed_out() {
+            return 0;
+        }
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+        let mut moves: Vec<_> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.captured().is_some() || mv.promote_to() == Some(PieceType::Queen))
+            .collect();
+        order(&mut moves);
+        for mv in &moves {
+            let score = -self.quiesce(&board.piece_moved(mv), -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+pub fn best_move(
+    board: &Board,
+    rules: &Rules,
+    difficulty: Difficulty,
+    limit: Duration,
+) -> Option<Move> {
+    let board = board.without_history();
+    let mut moves = board.legal_moves();
+    if moves.len() <= 1 {
+        return moves.pop();
+    }
+    order(&mut moves);
+    let mut rng = rand::thread_rng();
+    let mut moves: Vec<_> = moves
+        .into_iter()
+        .map(|mv| (mv, rng.gen_range(0..=difficulty.noise())))
+        .collect();
+
+    let mut search = Search::new(rules, difficulty.card_plies(), Some(limit));
+    for depth in 1..=difficulty.max_depth() {
+        let mut alpha = -INFINITY;
+        let mut iteration_best = 0;
+        for (i, (mv, noise)) in moves.iter().enumerate() {
+            let child = board.piece_moved(mv);
+            let score = -search.after_move(&child, depth - 1, -INFINITY, noise - alpha, 1) + noise;
+            if search.stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                iteration_best = i;
+            }
+        }
+        if search.stopped {
+            break;
+        }
+        let mv = moves.remove(iteration_best);
+        moves.insert(0, mv);
+        if alpha >= MATE - 64 {
+            break;
+        }
+    }
+    Some(moves.swap_remove(0).0)
+}
+
+pub fn choose_card(
+    board: &Board,
+    rules: &Rules,
+    difficulty: Difficulty,
+    limit: Duration,
+) -> Option<usize> {
+    let board = board.without_history();
+    let mut search = Search::new(rules, 0, Some(limit));
+    let mut rng = rand::thread_rng();
+    let mut values = BTreeMap::new();
+    let mut best = (search.assess(&board), None);
+    for (i, card) in board.hand(board.active).iter().enumerate() {
+        let value = *values.entry(*card).or_insert_with(|| {
+            let cands = board.event_candidates(*card);
+            if cands.is_empty() {
+                return None;
+            }
+            let values = cands
+                .iter()
+                .map(|ev| search.assess(&board.event_applied(*ev)));
+            if rules.choose_events {
+                values.max()
+            } else {
+                Some(values.sum::<i32>() / cands.len() as i32)
+            }
+        });
+        if search.stopped {
+            break;
+        }
+        let Some(value) = value else {
+            continue;
+        };
+        let value = value + rng.gen_range(0..=difficulty.noise());
+        if value > best.0 {
+            best = (value, Some(i));
+        }
+    }
+    best.1
+}
+
+pub fn choose_event(
+    board: &Board,
+    rules: &Rules,
+    card: Card,
+    difficulty: Difficulty,
+    limit: Duration,
+) -> Option<usize> {
+    let board = board.without_history();
+    let mut search = Search::new(rules, 0, Some(limit));
+    let mut rng = rand::thread_rng();
+    let mut best = None;
+    for (i, ev) in board.event_candidates(card).iter().enumerate() {
+        let value = search.assess(&board.event_applied(*ev));
+        if search.stopped {
+            break;
+        }
+        let value = value + rng.gen_range(0..=difficulty.noise());
+        if best.map_or(true, |(_, best)| value > best) {
+            best = Some((i, value));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+// A draw is only worth taking when `color` stands clearly worse.
+pub fn accepts_draw(
+    board: &Board,
+    rules: &Rules,
+    color: Color,
+    difficulty: Difficulty,
+    limit: Duration,
+) -> bool {
+    let board = board.without_history();
+    let mut search = Search::new(rules, 0, Some(limit));
+    let score = search.negamax(&board, 2, -INFINITY, INFINITY, 0);
+    if search.stopped {
+        return false;
+    }
+    let score = if board.active == color { score } else { -score };
+    score + rand::thread_rng().gen_range(0..=difficulty.noise()) < -DRAW_MARGIN
+}
@@ -1,33 +1,200 @@
-use ::chess::*;
+use ::chess::{
+    ai::Difficulty,
+    clock::{Bonus, TimeControl},
+    deck::DeckConfig,
+    *,
+};
 use actix::*;
 use actix_files::Files;
 use actix_web::*;
 use actix_web_actors::ws;
-use std::time::Instant;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+const MAX_BASE: u64 = 3 * 60 * 60;
+const MAX_BONUS: u64 = 60;
+
+#[derive(Deserialize)]
+struct WsQuery {
+    admin: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RoomQuery {
+    base: Option<u64>,
+    increment: Option<u64>,
+    delay: Option<u64>,
+    deck: Option<String>,
+    choose_cards: Option<bool>,
+    choose_events: Option<bool>,
+    seed: Option<u64>,
+    ai: Option<Difficulty>,
+}
+
+impl RoomQuery {
+    fn config(&self, deck: Option<DeckConfig>) -> Option<server::Config> {
+        let time_control = match (self.base, self.increment, self.delay) {
+            (None, None, None) => None,
+            (Some(base), increment, delay) if base > 0 && base <= MAX_BASE => {
+                if increment.or(delay).is_some_and(|bonus| bonus > MAX_BONUS) {
+                    return None;
+                }
+                let bonus = match (increment, delay) {
+                    (None, None) => None,
+                    (Some(inc), None) => Some(Bonus::Fischer(Duration::from_secs(inc))),
+                    (None, Some(delay)) => Some(Bonus::Bronstein(Duration::from_secs(delay))),
+                    _ => return None,
+                };
+                Some(TimeControl {
+                    base: Duration::from_secs(base),
+                    bonus,
+                })
+            }
+            _ => return None,
+        };
+        let deck = match (deck, &self.deck) {
+            (Some(deck), _) => deck,
+            (None, Some(name)) => DeckConfig::preset(name)?,
+            (None, None) => DeckConfig::default(),
+        };
+        if !deck.is_valid() {
+            return None;
+        }
+        Some(server::Config {
+            time_control,
+            deck,
+            choose_cards: self.choose_cards.unwrap_or(false),
+            choose_events: self.choose_events.unwrap_or(false),
+            seed: self.seed,
+            ai: self.ai,
+            ..server::Config::default()
+        })
+    }
+}
 
 async fn ws_route(
     req: HttpRequest,
     stream: web::Payload,
-    srv: web::Data<Addr<server::Server>>,
+    room: web::Path<String>,
+    query: web::Query<WsQuery>,
+    lobby: web::Data<Addr<lobby::Lobby>>,
 ) -> Result<HttpResponse, Error> {
+    let id = room.into_inner();
+    let room = lobby
+        .send(lobby::GetRoom { id })
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let Some(addr) = room else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let admin = match (&query.admin, std::env::var("ADMIN_TOKEN")) {
+        (Some(token), Ok(expected)) => !expected.is_empty() && *token == expected,
+        _ => false,
+    };
     ws::start(
         session::WsSession {
             id: 0,
             hb: Instant::now(),
-            addr: srv.get_ref().clone(),
+            admin,
+            name: query.into_inner().name,
+            addr,
         },
         &req,
         stream,
     )
 }
 
+async fn export_pgn(
+    room: web::Path<String>,
+    lobby: web::Data<Addr<lobby::Lobby>>,
+) -> Result<HttpResponse, Error> {
+    let id = room.into_inner();
+    let room = lobby
+        .send(lobby::GetRoom { id })
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let Some(addr) = room else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let pgn = addr
+        .send(server::ExportPgn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-chess-pgn")
+        .body(pgn))
+}
+
+async fn import_pgn(
+    body: String,
+    lobby: web::Data<Addr<lobby::Lobby>>,
+) -> Result<HttpResponse, Error> {
+    let record = match pgn::import(&body) {
+        Ok(record) => record,
+        Err(err) => return Ok(HttpResponse::BadRequest().body(err.to_string())),
+    };
+    let config = server::Config {
+        record: Some(record),
+        ..server::Config::default()
+    };
+    let id = lobby
+        .send(lobby::CreateRoom { config })
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({ "id": id })))
+}
+
+async fn list_cards() -> HttpResponse {
+    let cards: Vec<_> = chess::Card::all().map(chess::Card::info).collect();
+    HttpResponse::Ok().json(cards)
+}
+
+async fn list_decks() -> HttpResponse {
+    let decks: Vec<_> = DeckConfig::PRESETS
+        .iter()
+        .filter_map(|name| DeckConfig::preset(name))
+        .collect();
+    HttpResponse::Ok().json(decks)
+}
+
+async fn list_rooms(lobby: web::Data<Addr<lobby::Lobby>>) -> Result<HttpResponse, Error> {
+    let rooms = lobby
+        .send(lobby::ListRooms)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(rooms))
+}
+
+async fn create_room(
+    query: web::Query<RoomQuery>,
+    deck: Option<web::Json<DeckConfig>>,
+    lobby: web::Data<Addr<lobby::Lobby>>,
+) -> Result<HttpResponse, Error> {
+    let Some(config) = query.config(deck.map(web::Json::into_inner)) else {
+        return Ok(HttpResponse::BadRequest().finish());
+    };
+    let id = lobby
+        .send(lobby::CreateRoom { config })
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({ "id": id })))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let server = server::Server::default().start();
+    effect::register(house::PawnRetreat);
+    let lobby = lobby::Lobby::default().start();
     HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(server.clone()))
-            .route("/ws", web::get().to(ws_route))
+            .app_data(web::Data::new(lobby.clone()))
+            .route("/ws/{room_id}", web::get().to(ws_route))
+            .route("/cards", web::get().to(list_cards))
+            .route("/decks", web::get().to(list_decks))
+            .route("/rooms", web::get().to(list_rooms))
+            .route("/rooms", web::post().to(create_room))
+            .route("/rooms/import", web::post().to(import_pgn))
+            .route("/rooms/{room_id}/pgn", web::get().to(export_pgn))
             .service(web::redirect("/", "/index.html"))
             .service(Files::new("/", "./static"))
     })
@@ -0,0 +1,34 @@
+use ::chess::chess::Board;
+use std::time::Instant;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(depth) = args.next().and_then(|arg| arg.parse().ok()) else {
+        eprintln!("usage: perft <depth> [fen]");
+        std::process::exit(1);
+    };
+    let fen: Vec<_> = args.collect();
+    let board = if fen.is_empty() {
+        Board::default()
+    } else {
+        match Board::from_fen(&fen.join(" ")) {
+            Ok(board) => board,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    let start = Instant::now();
+    let mut divide = board.divide(depth);
+    divide.sort_by_key(|(mv, _)| mv.to_string());
+    let mut total = 0;
+    for (mv, nodes) in divide {
+        println!("{}: {}", mv, nodes);
+        total += nodes;
+    }
+    println!();
+    println!("Nodes: {}", total);
+    println!("Time: {:?}", start.elapsed());
+}
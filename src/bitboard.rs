@@ -0,0 +1,250 @@
+use crate::chess::Color;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self(0);
+
+    #[inline]
+    pub const fn square(sq: u8) -> Self {
+        Self(1 << sq)
+    }
+
+    #[inline]
+    pub fn contains(self, sq: u8) -> bool {
+        self.0 & (1 << sq) != 0
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    #[inline]
+    pub fn lsb(self) -> Option<u8> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as u8)
+        }
+    }
+}
+
+impl Iterator for Bitboard {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        let sq = self.lsb()?;
+        self.0 &= self.0 - 1;
+        Some(sq)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    #[inline]
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+const KNIGHT_DELTAS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_DELTAS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+// Ray directions in the same order as KING_DELTAS: N, NE, E, SE, S, SW, W, NW.
+const POSITIVE: [bool; 8] = [true, true, true, false, false, false, false, true];
+const ROOK_DIRS: [usize; 4] = [0, 2, 4, 6];
+const BISHOP_DIRS: [usize; 4] = [1, 3, 5, 7];
+
+const fn leaper_table(deltas: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let file = (sq % 8) as i8;
+        let rank = (sq / 8) as i8;
+        let mut i = 0;
+        while i < deltas.len() {
+            let f = file + deltas[i].0;
+            let r = rank + deltas[i].1;
+            if f >= 0 && f < 8 && r >= 0 && r < 8 {
+                table[sq] |= 1 << (r * 8 + f);
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    table
+}
+
+const fn ray_table() -> [[u64; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut dir = 0;
+    while dir < 8 {
+        let (df, dr) = KING_DELTAS[dir];
+        let mut sq = 0;
+        while sq < 64 {
+            let mut f = (sq % 8) as i8 + df;
+            let mut r = (sq / 8) as i8 + dr;
+            while f >= 0 && f < 8 && r >= 0 && r < 8 {
+                table[dir][sq] |= 1 << (r * 8 + f);
+                f += df;
+                r += dr;
+            }
+            sq += 1;
+        }
+        dir += 1;
+    }
+    table
+}
+
+const KNIGHT: [u64; 64] = leaper_table(&KNIGHT_DELTAS);
+const KING: [u64; 64] = leaper_table(&KING_DELTAS);
+const PAWN: [[u64; 64]; 2] = [
+    leaper_table(&[(1, 1), (-1, 1)]),
+    leaper_table(&[(1, -1), (-1, -1)]),
+];
+const RAYS: [[u64; 64]; 8] = ray_table();
+
+#[inline]
+pub fn knight_attacks(sq: u8) -> Bitboard {
+    Bitboard(KNIGHT[sq as usize])
+}
+
+#[inline]
+pub fn king_attacks(sq: u8) -> Bitboard {
+    Bitboard(KING[sq as usize])
+}
+
+#[inline]
+pub fn pawn_attacks(color: Color, sq: u8) -> Bitboard {
+    Bitboard(PAWN[!color.is_white() as usize][sq as usize])
+}
+
+#[inline]
+fn ray_attacks(dir: usize, sq: u8, occupied: Bitboard) -> u64 {
+    let ray = RAYS[dir][sq as usize];
+    let blockers = ray & occupied.0;
+    if blockers == 0 {
+        return ray;
+    }
+    let blocker = if POSITIVE[dir] {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[dir][blocker as usize]
+}
+
+#[inline]
+pub fn bishop_attacks(sq: u8, occupied: Bitboard) -> Bitboard {
+    Bitboard(
+        BISHOP_DIRS
+            .iter()
+            .fold(0, |acc, dir| acc | ray_attacks(*dir, sq, occupied)),
+    )
+}
+
+#[inline]
+pub fn rook_attacks(sq: u8, occupied: Bitboard) -> Bitboard {
+    Bitboard(
+        ROOK_DIRS
+            .iter()
+            .fold(0, |acc, dir| acc | ray_attacks(*dir, sq, occupied)),
+    )
+}
+
+pub fn between(a: u8, b: u8) -> Bitboard {
+    for rays in &RAYS {
+        let ray = rays[a as usize];
+        if ray & (1 << b) != 0 {
+            return Bitboard(ray & !rays[b as usize] & !(1 << b));
+        }
+    }
+    Bitboard::EMPTY
+}
+
+pub fn line(a: u8, b: u8) -> Bitboard {
+    for dir in 0..8 {
+        let ray = RAYS[dir][a as usize];
+        if ray & (1 << b) != 0 {
+            return Bitboard(ray | RAYS[(dir + 4) % 8][a as usize] | (1 << a));
+        }
+    }
+    Bitboard::EMPTY
+}
@@ -1,8 +1,13 @@
+use crate::{
+    bitboard::*,
+    deck::DeckConfig,
+    effect::{self, CardEffect, CustomEvent},
+};
 use rand::{seq::SliceRandom, *};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
-enum PieceType {
+pub enum PieceType {
     Pawn,
     Knight,
     Bishop,
@@ -26,7 +31,7 @@ impl std::fmt::Display for Color {
 
 impl Color {
     #[inline]
-    fn other(self) -> Self {
+    pub fn other(self) -> Self {
         match self {
             Color::White => Color::Black,
             Color::Black => Color::White,
@@ -37,6 +42,11 @@ impl Color {
     pub fn is_white(self) -> bool {
         self == Color::White
     }
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -45,20 +55,31 @@ pub struct Piece {
     color: Color,
 }
 
+impl PieceType {
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn letter(self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Rook => 'R',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+}
+
 impl std::fmt::Display for Piece {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let c = match self.typ {
-            PieceType::Pawn => 'p',
-            PieceType::Knight => 'n',
-            PieceType::Bishop => 'b',
-            PieceType::Rook => 'r',
-            PieceType::Queen => 'q',
-            PieceType::King => 'k',
-        };
+        let c = self.typ.letter();
         let c = if self.color.is_white() {
-            c.to_ascii_uppercase()
-        } else {
             c
+        } else {
+            c.to_ascii_lowercase()
         };
         write!(f, "{}", c)
     }
@@ -70,33 +91,61 @@ impl Piece {
         Self { typ, color }
     }
 
+    fn from_char(c: char) -> Option<Self> {
+        let typ = match c.to_ascii_lowercase() {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Self::new(typ, color))
+    }
+
+    #[inline]
+    pub fn typ(self) -> PieceType {
+        self.typ
+    }
+
     #[inline]
-    fn is_pawn(self) -> bool {
+    pub fn color(self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    pub fn is_pawn(self) -> bool {
         self.typ == PieceType::Pawn
     }
 
     #[inline]
-    fn is_knight(self) -> bool {
+    pub fn is_knight(self) -> bool {
         self.typ == PieceType::Knight
     }
 
     #[inline]
-    fn is_bishop(self) -> bool {
+    pub fn is_bishop(self) -> bool {
         self.typ == PieceType::Bishop
     }
 
     #[inline]
-    fn is_rook(self) -> bool {
+    pub fn is_rook(self) -> bool {
         self.typ == PieceType::Rook
     }
 
     #[inline]
-    fn is_queen(self) -> bool {
+    pub fn is_queen(self) -> bool {
         self.typ == PieceType::Queen
     }
 
     #[inline]
-    fn is_king(self) -> bool {
+    pub fn is_king(self) -> bool {
         self.typ == PieceType::King
     }
 }
@@ -115,6 +164,22 @@ impl std::fmt::Display for Location {
     }
 }
 
+impl std::str::FromStr for Location {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let &[file, rank] = s.as_bytes() else {
+            return Err(());
+        };
+        let loc = Self::new(file as i8 - b'a' as i8, rank as i8 - b'1' as i8);
+        if loc.is_valid() {
+            Ok(loc)
+        } else {
+            Err(())
+        }
+    }
+}
+
 impl std::ops::Add<(i8, i8)> for Location {
     type Output = Self;
 
@@ -133,23 +198,35 @@ impl std::ops::Add<&(i8, i8)> for Location {
 
 impl Location {
     #[inline]
-    const fn new(file: i8, rank: i8) -> Self {
+    pub const fn new(file: i8, rank: i8) -> Self {
         Self { file, rank }
     }
 
     #[inline]
-    fn is_valid(self) -> bool {
+    pub fn file(self) -> i8 {
+        self.file
+    }
+
+    #[inline]
+    pub fn rank(self) -> i8 {
+        self.rank
+    }
+
+    #[inline]
+    pub fn is_valid(self) -> bool {
         let range = 0..8;
         range.contains(&self.file) && range.contains(&self.rank)
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum LocationState {
-    Invalid,
-    Empty,
-    Opponent(Piece),
-    Friendly,
+    #[inline]
+    fn square(self) -> u8 {
+        (self.rank * 8 + self.file) as u8
+    }
+
+    #[inline]
+    fn from_square(sq: u8) -> Self {
+        Self::new((sq % 8) as i8, (sq / 8) as i8)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -185,6 +262,41 @@ impl Move {
         self.castle = Some((rook_from, rook_to));
         self
     }
+
+    #[inline]
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+
+    #[inline]
+    pub fn from(&self) -> Location {
+        self.from
+    }
+
+    #[inline]
+    pub fn to(&self) -> Location {
+        self.to
+    }
+
+    #[inline]
+    pub fn captured(&self) -> Option<Piece> {
+        self.attack.map(|(_, piece)| piece)
+    }
+
+    #[inline]
+    pub fn promote_to(&self) -> Option<PieceType> {
+        self.promote_to
+    }
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(typ) = self.promote_to {
+            write!(f, "{}", typ.letter().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -192,9 +304,24 @@ pub enum GameState {
     Normal,
     Checkmate(Color),
     Stalemate,
+    FiftyMoves,
+    Repetition,
+    InsufficientMaterial,
+    Resigned(Color),
+    DrawAgreed,
+    Timeout(Color),
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Position {
+    colors: [Bitboard; 2],
+    types: [Bitboard; 6],
+    active: Color,
+    castles: [bool; 4],
+    en_passant: Option<Location>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Event {
     Swap(Location, Location),
     KnightToBishop(Location),
@@ -206,28 +333,350 @@ pub enum Event {
     QueenToPawns(Location, i8),
     Rotate(Location, Location),
     KingMove(Location, Location),
+    DemoteQueen(Location),
+    Freeze(Location),
+    PushBack(Location, Location),
+    Custom(CustomEvent),
+}
+
+impl Event {
+    pub fn card(&self) -> Card {
+        match self {
+            Event::Swap(..) => Card::Swap,
+            Event::KnightToBishop(_) => Card::KnightToBishop,
+            Event::BishopToKnight(_) => Card::BishopToKnight,
+            Event::RooksToQueen(..) => Card::RooksToQueen,
+            Event::QueenToRooks(..) => Card::QueenToRooks,
+            Event::PawnRun(..) => Card::PawnRun,
+            Event::PawnsToQueen(_) => Card::PawnsToQueen,
+            Event::QueenToPawns(..) => Card::QueenToPawns,
+            Event::Rotate(..) => Card::Rotate,
+            Event::KingMove(..) => Card::KingMove,
+            Event::DemoteQueen(_) => Card::DemoteQueen,
+            Event::Freeze(_) => Card::Freeze,
+            Event::PushBack(..) => Card::PushBack,
+            Event::Custom(ev) => ev.card(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Event::Custom(ev) => match ev.card().effect() {
+                Some(effect) => effect.describe(ev),
+                None => self.to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Swap(l1, l2) => write!(f, "Swap {} {}", l1, l2),
+            Event::KnightToBishop(l) => write!(f, "KnightToBishop {}", l),
+            Event::BishopToKnight(l) => write!(f, "BishopToKnight {}", l),
+            Event::RooksToQueen(l1, l2) => write!(f, "RooksToQueen {} {}", l1, l2),
+            Event::QueenToRooks(l1, l2) => write!(f, "QueenToRooks {} {}", l1, l2),
+            Event::PawnRun(l1, l2) => write!(f, "PawnRun {} {}", l1, l2),
+            Event::PawnsToQueen(ls) => {
+                write!(f, "PawnsToQueen")?;
+                for l in ls {
+                    write!(f, " {}", l)?;
+                }
+                Ok(())
+            }
+            Event::QueenToPawns(l, rank) => write!(f, "QueenToPawns {} {}", l, rank + 1),
+            Event::Rotate(l1, l2) => write!(f, "Rotate {} {}", l1, l2),
+            Event::KingMove(l1, l2) => write!(f, "KingMove {} {}", l1, l2),
+            Event::DemoteQueen(l) => write!(f, "DemoteQueen {}", l),
+            Event::Freeze(l) => write!(f, "Freeze {}", l),
+            Event::PushBack(l1, l2) => write!(f, "PushBack {} {}", l1, l2),
+            Event::Custom(ev) => {
+                write!(f, "{}", ev.card())?;
+                let args = match ev.card().effect() {
+                    Some(effect) => effect.serialize(ev),
+                    None => String::new(),
+                };
+                if !args.is_empty() {
+                    write!(f, " {}", args)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Event {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or(())?;
+        let args: Vec<_> = words.collect();
+        let loc = |i: usize| -> Result<Location, ()> { args[i].parse() };
+        let event = match (name, args.len()) {
+            ("Swap", 2) => Event::Swap(loc(0)?, loc(1)?),
+            ("KnightToBishop", 1) => Event::KnightToBishop(loc(0)?),
+            ("BishopToKnight", 1) => Event::BishopToKnight(loc(0)?),
+            ("RooksToQueen", 2) => Event::RooksToQueen(loc(0)?, loc(1)?),
+            ("QueenToRooks", 2) => Event::QueenToRooks(loc(0)?, loc(1)?),
+            ("PawnRun", 2) => Event::PawnRun(loc(0)?, loc(1)?),
+            ("PawnsToQueen", 8) => {
+                let mut ls = [Location::new(0, 0); 8];
+                for (i, l) in ls.iter_mut().enumerate() {
+                    *l = loc(i)?;
+                }
+                Event::PawnsToQueen(ls)
+            }
+            ("QueenToPawns", 2) => {
+                let rank: i8 = args[1].parse().map_err(|_| ())?;
+                if !(1..=8).contains(&rank) {
+                    return Err(());
+                }
+                Event::QueenToPawns(loc(0)?, rank - 1)
+            }
+            ("Rotate", 2) => Event::Rotate(loc(0)?, loc(1)?),
+            ("KingMove", 2) => Event::KingMove(loc(0)?, loc(1)?),
+            ("DemoteQueen", 1) => Event::DemoteQueen(loc(0)?),
+            ("Freeze", 1) => Event::Freeze(loc(0)?),
+            ("PushBack", 2) => Event::PushBack(loc(0)?, loc(1)?),
+            _ => {
+                let card: Card = name.parse()?;
+                let ev = card.effect().ok_or(())?.deserialize(&args).ok_or(())?;
+                if ev.card() != card {
+                    return Err(());
+                }
+                Event::Custom(ev)
+            }
+        };
+        Ok(event)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Card {
+    Blank,
+    BishopToKnight,
+    KnightToBishop,
+    RooksToQueen,
+    QueenToRooks,
+    PawnsToQueen,
+    QueenToPawns,
+    Swap,
+    Rotate,
+    PawnRun,
+    KingMove,
+    DemoteQueen,
+    Freeze,
+    PushBack,
+    Custom(&'static str),
+}
+
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Card::Custom(id) => write!(f, "{}", id),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl std::str::FromStr for Card {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Card::all().find(|card| card.to_string() == s).ok_or(())
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| serde::de::Error::custom(format!("unknown card {}", s)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CardInfo {
+    pub card: Card,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+struct CardEntry {
+    card: Card,
+    name: &'static str,
+    description: &'static str,
+    generate: fn(&Board) -> Vec<Event>,
+}
+
+const CARDS: [CardEntry; 14] = [
+    CardEntry {
+        card: Card::Blank,
+        name: "효과 없음",
+        description: "아무 일도 일어나지 않습니다.",
+        generate: |_| vec![],
+    },
+    CardEntry {
+        card: Card::BishopToKnight,
+        name: "말에 오르다",
+        description: "비숍 하나가 나이트가 됩니다.",
+        generate: Board::make_b2n,
+    },
+    CardEntry {
+        card: Card::KnightToBishop,
+        name: "말에서 내리다",
+        description: "나이트 하나가 비숍이 됩니다.",
+        generate: Board::make_n2b,
+    },
+    CardEntry {
+        card: Card::RooksToQueen,
+        name: "융합",
+        description: "룩 두 개가 퀸 하나가 됩니다.",
+        generate: Board::make_r2q,
+    },
+    CardEntry {
+        card: Card::QueenToRooks,
+        name: "분열",
+        description: "퀸 하나가 룩 두 개가 됩니다.",
+        generate: Board::make_q2r,
+    },
+    CardEntry {
+        card: Card::PawnsToQueen,
+        name: "합체",
+        description: "폰 여덟 개가 퀸 하나가 됩니다.",
+        generate: Board::make_p2q,
+    },
+    CardEntry {
+        card: Card::QueenToPawns,
+        name: "산산조각",
+        description: "퀸 하나가 빈 가로줄을 채우는 폰 여덟 개가 됩니다.",
+        generate: Board::make_q2p,
+    },
+    CardEntry {
+        card: Card::Swap,
+        name: "교대 근무",
+        description: "종류가 다른 두 기물의 위치가 바뀝니다.",
+        generate: Board::make_swap,
+    },
+    CardEntry {
+        card: Card::Rotate,
+        name: "지구는 둥그니까",
+        description: "가장자리의 기물이 체스판 반대편으로 건너갑니다.",
+        generate: Board::make_rotate,
+    },
+    CardEntry {
+        card: Card::PawnRun,
+        name: "전력 질주",
+        description: "폰 하나가 막힐 때까지 앞으로 달려갑니다.",
+        generate: Board::make_pawn_run,
+    },
+    CardEntry {
+        card: Card::KingMove,
+        name: "이사",
+        description: "킹이 막힐 때까지 반대편으로 걸어갑니다.",
+        generate: Board::make_king_move,
+    },
+    CardEntry {
+        card: Card::DemoteQueen,
+        name: "강등",
+        description: "상대 퀸 하나가 룩이 됩니다.",
+        generate: Board::make_demote_queen,
+    },
+    CardEntry {
+        card: Card::Freeze,
+        name: "얼음",
+        description: "상대 기물 하나가 한 턴 동안 움직이지 못합니다.",
+        generate: Board::make_freeze,
+    },
+    CardEntry {
+        card: Card::PushBack,
+        name: "밀어내기",
+        description: "상대 폰 하나가 한 칸 뒤로 밀려납니다.",
+        generate: Board::make_push_back,
+    },
+];
+
+impl Card {
+    fn entry(self) -> Option<&'static CardEntry> {
+        CARDS.iter().find(|entry| entry.card == self)
+    }
+
+    pub fn effect(self) -> Option<&'static dyn CardEffect> {
+        match self {
+            Card::Custom(id) => effect::lookup(id),
+            _ => None,
+        }
+    }
+
+    pub fn builtin() -> impl Iterator<Item = Card> {
+        CARDS.iter().map(|entry| entry.card)
+    }
+
+    pub fn all() -> impl Iterator<Item = Card> {
+        Self::builtin().chain(effect::registered())
+    }
+
+    pub fn name(self) -> &'static str {
+        match (self.entry(), self.effect()) {
+            (Some(entry), _) => entry.name,
+            (_, Some(effect)) => effect.name(),
+            _ => "",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match (self.entry(), self.effect()) {
+            (Some(entry), _) => entry.description,
+            (_, Some(effect)) => effect.description(),
+            _ => "",
+        }
+    }
+
+    pub fn info(self) -> CardInfo {
+        CardInfo {
+            card: self,
+            name: self.name(),
+            description: self.description(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Board {
-    pub pieces: [[Option<Piece>; 8]; 8],
+    squares: [Option<Piece>; 64],
+    colors: [Bitboard; 2],
+    types: [Bitboard; 6],
     pub active: Color,
     wk_castle: bool,
     wq_castle: bool,
     bk_castle: bool,
     bq_castle: bool,
     en_passant: Option<Location>,
-    pub half_moves: usize,
+    pub plies: usize,
+    pub halfmove_clock: usize,
+    pub fullmove_number: usize,
     pub last_move: Option<Move>,
     pub last_event: Option<Event>,
-    pub last_card: Option<usize>,
-    pub white_cards: Vec<usize>,
-    pub black_cards: Vec<usize>,
+    pub last_card: Option<Card>,
+    pub white_cards: Vec<Card>,
+    pub black_cards: Vec<Card>,
+    frozen: Option<(Location, Piece)>,
+    history: Vec<Position>,
+    outcome: Option<GameState>,
 }
 
 impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, rank) in self.pieces.iter().enumerate().rev() {
+        for (i, rank) in self.pieces().iter().enumerate().rev() {
             let mut empty = 0;
             for piece in rank.iter() {
                 match piece {
@@ -248,49 +697,249 @@ impl std::fmt::Display for Board {
                 write!(f, "/")?;
             }
         }
-        write!(f, " {}", self.active)?;
-        let wk_castle = if self.wk_castle { 'K' } else { '-' };
-        let wq_castle = if self.wq_castle { 'Q' } else { '-' };
-        let bk_castle = if self.bk_castle { 'k' } else { '-' };
-        let bq_castle = if self.bq_castle { 'q' } else { '-' };
-        write!(f, " {}{}{}{}", wk_castle, wq_castle, bk_castle, bq_castle)?;
+        write!(f, " {} ", self.active)?;
+        let castles = [
+            (self.wk_castle, 'K'),
+            (self.wq_castle, 'Q'),
+            (self.bk_castle, 'k'),
+            (self.bq_castle, 'q'),
+        ];
+        if castles.iter().any(|(castle, _)| *castle) {
+            for (castle, c) in castles {
+                if castle {
+                    write!(f, "{}", c)?;
+                }
+            }
+        } else {
+            write!(f, "-")?;
+        }
         let en_passant = match self.en_passant {
             Some(loc) => loc.to_string(),
             None => "-".to_string(),
         };
-        write!(f, " {} 0 1", en_passant)
+        write!(
+            f,
+            " {} {} {}",
+            en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanError {
+    Syntax,
+    IllegalMove,
+    Ambiguous,
+}
+
+impl std::fmt::Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SanError::Syntax => "malformed move",
+            SanError::IllegalMove => "no legal move matches",
+            SanError::Ambiguous => "more than one legal move matches",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::error::Error for SanError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenError {
+    FieldCount,
+    Placement,
+    ActiveColor,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            FenError::FieldCount => "wrong number of fields",
+            FenError::Placement => "invalid piece placement",
+            FenError::ActiveColor => "invalid active color",
+            FenError::Castling => "invalid castling availability",
+            FenError::EnPassant => "invalid en passant square",
+            FenError::HalfmoveClock => "invalid halfmove clock",
+            FenError::FullmoveNumber => "invalid fullmove number",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl std::str::FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_fen(s)
     }
 }
 
 impl Default for Board {
     #[inline]
     fn default() -> Self {
-        Self {
-            pieces: INIT_PIECES,
+        let mut board = Self {
+            squares: [None; 64],
+            colors: [Bitboard::EMPTY; 2],
+            types: [Bitboard::EMPTY; 6],
             active: Color::White,
             wk_castle: true,
             wq_castle: true,
             bk_castle: true,
             bq_castle: true,
             en_passant: None,
-            half_moves: 0,
+            plies: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
             last_move: None,
             last_event: None,
             last_card: None,
-            white_cards: vec![0, 0],
-            black_cards: vec![0, 0],
-        }
+            white_cards: vec![Card::Blank, Card::Blank],
+            black_cards: vec![Card::Blank, Card::Blank],
+            frozen: None,
+            history: vec![],
+            outcome: None,
+        };
+        board.set_pieces(&INIT_PIECES);
+        board.history.push(board.position());
+        board
     }
 }
 
 impl Board {
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<_> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::FieldCount);
+        }
+
+        let mut pieces = [[None; 8]; 8];
+        let ranks: Vec<_> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::Placement);
+        }
+        for (i, rank) in ranks.into_iter().enumerate() {
+            let row = &mut pieces[7 - i];
+            let mut file = 0;
+            for c in rank.chars() {
+                if let Some(n) = c.to_digit(10) {
+                    if n == 0 || n > 8 {
+                        return Err(FenError::Placement);
+                    }
+                    file += n as usize;
+                } else {
+                    let piece = Piece::from_char(c).ok_or(FenError::Placement)?;
+                    if file >= 8 {
+                        return Err(FenError::Placement);
+                    }
+                    row[file] = Some(piece);
+                    file += 1;
+                }
+                if file > 8 {
+                    return Err(FenError::Placement);
+                }
+            }
+            if file != 8 {
+                return Err(FenError::Placement);
+            }
+        }
+
+        let active = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::ActiveColor),
+        };
+
+        let mut board = Self {
+            active,
+            wk_castle: false,
+            wq_castle: false,
+            bk_castle: false,
+            bq_castle: false,
+            ..Self::default()
+        };
+        board.set_pieces(&pieces);
+        for color in [Color::White, Color::Black] {
+            let kings = board
+                .iter_pieces_of(color)
+                .filter(|(_, p)| p.is_king())
+                .count();
+            if kings != 1 {
+                return Err(FenError::Placement);
+            }
+        }
+
+        if fields[2].is_empty() {
+            return Err(FenError::Castling);
+        }
+        for c in fields[2].chars() {
+            let castle = match c {
+                'K' => &mut board.wk_castle,
+                'Q' => &mut board.wq_castle,
+                'k' => &mut board.bk_castle,
+                'q' => &mut board.bq_castle,
+                '-' => continue,
+                _ => return Err(FenError::Castling),
+            };
+            if *castle {
+                return Err(FenError::Castling);
+            }
+            *castle = true;
+        }
+        board.revoke_castles();
+
+        if fields[3] != "-" {
+            let loc: Location = fields[3].parse().map_err(|_| FenError::EnPassant)?;
+            let rank = if active.is_white() { 5 } else { 2 };
+            if loc.rank != rank {
+                return Err(FenError::EnPassant);
+            }
+            board.en_passant = Some(loc);
+        }
+
+        if fields.len() == 6 {
+            board.halfmove_clock = fields[4].parse().map_err(|_| FenError::HalfmoveClock)?;
+            board.fullmove_number = fields[5].parse().map_err(|_| FenError::FullmoveNumber)?;
+            if board.fullmove_number == 0 {
+                return Err(FenError::FullmoveNumber);
+            }
+        }
+        board.plies = (board.fullmove_number - 1) * 2 + !active.is_white() as usize;
+
+        board.history = vec![board.position()];
+        Ok(board)
+    }
+
+    pub fn pieces(&self) -> [[Option<Piece>; 8]; 8] {
+        let mut pieces = [[None; 8]; 8];
+        for (sq, piece) in self.squares.iter().enumerate() {
+            pieces[sq / 8][sq % 8] = *piece;
+        }
+        pieces
+    }
+
+    fn set_pieces(&mut self, pieces: &[[Option<Piece>; 8]; 8]) {
+        self.squares = [None; 64];
+        self.colors = [Bitboard::EMPTY; 2];
+        self.types = [Bitboard::EMPTY; 6];
+        for (rank, row) in pieces.iter().enumerate() {
+            for (file, piece) in row.iter().enumerate() {
+                self.set_piece(Location::new(file as i8, rank as i8), *piece);
+            }
+        }
+    }
+
     fn iter_locations(&self) -> impl Iterator<Item = (Location, Option<&Piece>)> {
-        self.pieces.iter().enumerate().flat_map(|(rank, pieces)| {
-            pieces.iter().enumerate().map(move |(file, piece)| {
-                let loc = Location::new(file as i8, rank as i8);
-                (loc, piece.as_ref())
-            })
-        })
+        self.squares
+            .iter()
+            .enumerate()
+            .map(|(sq, piece)| (Location::from_square(sq as u8), piece.as_ref()))
     }
 
     fn iter_empty_locations(&self) -> impl Iterator<Item = (Location, Option<&Piece>)> {
@@ -304,86 +953,357 @@ impl Board {
         })
     }
 
-    fn iter_pieces_of(&self, color: Color) -> impl Iterator<Item = (Location, &Piece)> {
-        self.iter_pieces()
-            .filter(move |(_, piece)| piece.color == color)
+    pub fn iter_pieces_of(&self, color: Color) -> impl Iterator<Item = (Location, &Piece)> {
+        self.colors[color.index()].map(|sq| {
+            let piece = self.squares[sq as usize].as_ref().unwrap();
+            (Location::from_square(sq), piece)
+        })
     }
 
-    fn iter_active_pieces(&self) -> impl Iterator<Item = (Location, &Piece)> {
+    pub fn iter_active_pieces(&self) -> impl Iterator<Item = (Location, &Piece)> {
         self.iter_pieces_of(self.active)
     }
 
-    fn piece(&self, loc: Location) -> Option<Piece> {
-        self.pieces[loc.rank as usize][loc.file as usize]
+    #[inline]
+    pub fn piece(&self, loc: Location) -> Option<Piece> {
+        self.squares[loc.square() as usize]
     }
 
-    fn is_empty(&self, loc: Location) -> bool {
+    #[inline]
+    pub fn is_empty(&self, loc: Location) -> bool {
         self.piece(loc).is_none()
     }
 
-    fn set_piece(&mut self, loc: Location, piece: Option<Piece>) {
-        self.pieces[loc.rank as usize][loc.file as usize] = piece;
+    pub fn set_piece(&mut self, loc: Location, piece: Option<Piece>) {
+        let sq = loc.square();
+        let bit = Bitboard::square(sq);
+        if let Some(old) = self.squares[sq as usize] {
+            self.colors[old.color.index()] ^= bit;
+            self.types[old.typ.index()] ^= bit;
+        }
+        if let Some(new) = piece {
+            self.colors[new.color.index()] |= bit;
+            self.types[new.typ.index()] |= bit;
+        }
+        self.squares[sq as usize] = piece;
+    }
+
+    #[inline]
+    fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
     }
 
-    fn get_state(&self, loc: Location, color: Color) -> LocationState {
-        if !loc.is_valid() {
-            return LocationState::Invalid;
-        }
-        match self.piece(loc) {
-            Some(piece) => {
-                if piece.color == color {
-                    LocationState::Friendly
-                } else {
-                    LocationState::Opponent(piece)
-                }
+    #[inline]
+    fn bitboard(&self, typ: PieceType, color: Color) -> Bitboard {
+        self.types[typ.index()] & self.colors[color.index()]
+    }
+
+    fn king_square(&self, color: Color) -> Option<u8> {
+        self.bitboard(PieceType::King, color).lsb()
+    }
+
+    fn attackers(&self, sq: u8, color: Color, occupied: Bitboard, removed: Bitboard) -> Bitboard {
+        let types = &self.types;
+        let diagonal = types[PieceType::Bishop.index()] | types[PieceType::Queen.index()];
+        let straight = types[PieceType::Rook.index()] | types[PieceType::Queen.index()];
+        let attackers = (pawn_attacks(color.other(), sq) & types[PieceType::Pawn.index()])
+            | (knight_attacks(sq) & types[PieceType::Knight.index()])
+            | (king_attacks(sq) & types[PieceType::King.index()])
+            | (bishop_attacks(sq, occupied) & diagonal)
+            | (rook_attacks(sq, occupied) & straight);
+        attackers & self.colors[color.index()] & !removed
+    }
+
+    fn pinned(&self, king: u8, color: Color) -> Bitboard {
+        let them = color.other();
+        let diagonal =
+            self.bitboard(PieceType::Bishop, them) | self.bitboard(PieceType::Queen, them);
+        let straight = self.bitboard(PieceType::Rook, them) | self.bitboard(PieceType::Queen, them);
+        let snipers = (bishop_attacks(king, Bitboard::EMPTY) & diagonal)
+            | (rook_attacks(king, Bitboard::EMPTY) & straight);
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = between(king, sniper) & self.occupied();
+            if blockers.count() == 1 {
+                pinned |= blockers & self.colors[color.index()];
             }
-            None => LocationState::Empty,
+        }
+        pinned
+    }
+
+    fn king_attacked_after(&self, mv: &Move) -> bool {
+        let color = mv.piece.color;
+        let mut occupied = self.occupied() & !Bitboard::square(mv.from.square());
+        let mut removed = Bitboard::EMPTY;
+        if let Some((loc, _)) = mv.attack {
+            removed |= Bitboard::square(loc.square());
+        }
+        if let Some((from, to)) = mv.castle {
+            removed |= Bitboard::square(from.square());
+            occupied &= !Bitboard::square(from.square());
+            occupied |= Bitboard::square(to.square());
+        }
+        occupied &= !removed;
+        occupied |= Bitboard::square(mv.to.square());
+        let king = if mv.piece.is_king() {
+            Some(mv.to.square())
+        } else {
+            self.king_square(color)
+        };
+        match king {
+            Some(king) => !self
+                .attackers(king, color.other(), occupied, removed)
+                .is_empty(),
+            None => false,
         }
     }
 
     pub fn get_check(&self) -> Option<Location> {
-        for (loc, piece) in self.iter_pieces_of(self.active.other()) {
-            let moves = self.possible_moves(loc, *piece, false);
-            for mv in moves {
-                if let Some((loc, piece)) = mv.attack {
-                    if piece.is_king() {
-                        return Some(loc);
+        let king = self.king_square(self.active)?;
+        let attackers = self.attackers(king, self.active.other(), self.occupied(), Bitboard::EMPTY);
+        if attackers.is_empty() {
+            None
+        } else {
+            Some(Location::from_square(king))
+        }
+    }
+
+    fn can_attack_king(&self, color: Color) -> bool {
+        let Some(king) = self.king_square(color.other()) else {
+            return false;
+        };
+        !self
+            .attackers(king, color, self.occupied(), Bitboard::EMPTY)
+            .is_empty()
+    }
+
+    pub fn all_possible_moves(&self) -> Vec<(Location, Vec<Move>)> {
+        let moves = self.generate_moves();
+        match self.frozen() {
+            Some(frozen) if moves.iter().any(|(loc, _)| *loc != frozen) => moves
+                .into_iter()
+                .filter(|(loc, _)| *loc != frozen)
+                .collect(),
+            _ => moves,
+        }
+    }
+
+    pub fn frozen(&self) -> Option<Location> {
+        let (loc, piece) = self.frozen?;
+        if self.piece(loc) == Some(piece) {
+            Some(loc)
+        } else {
+            None
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        for (_, mvs) in self.all_possible_moves() {
+            for mv in mvs {
+                if mv.is_promotion {
+                    for typ in PROMOTIONS {
+                        let mut mv = mv.clone();
+                        mv.promote_to = Some(typ);
+                        moves.push(mv);
                     }
+                } else {
+                    moves.push(mv);
                 }
             }
         }
-        None
+        moves
     }
 
-    fn can_attack_king(&self, color: Color) -> bool {
-        for (loc, piece) in self.iter_pieces_of(color) {
-            let moves = self.possible_moves(loc, *piece, false);
-            for mv in moves {
-                if let Some((_, piece)) = mv.attack {
-                    if piece.is_king() {
-                        return true;
+    pub fn find_move(
+        &self,
+        from: Location,
+        to: Location,
+        promote_to: Option<PieceType>,
+    ) -> Option<Move> {
+        let (_, moves) = self
+            .all_possible_moves()
+            .into_iter()
+            .find(|(loc, _)| *loc == from)?;
+        let mut mv = moves.into_iter().find(|mv| mv.to == to)?;
+        if mv.is_promotion {
+            match promote_to? {
+                PieceType::Pawn | PieceType::King => return None,
+                typ => mv.promote_to = Some(typ),
+            }
+        } else if promote_to.is_some() {
+            return None;
+        }
+        Some(mv)
+    }
+
+    pub fn san(&self, mv: &Move) -> String {
+        let mut san = String::new();
+        if let Some((rook_from, _)) = mv.castle {
+            let castle = if rook_from.file > mv.from.file {
+                "O-O"
+            } else {
+                "O-O-O"
+            };
+            san.push_str(castle);
+        } else {
+            if !mv.piece.is_pawn() {
+                san.push(mv.piece.typ.letter());
+                let others: Vec<_> = self
+                    .iter_pieces_of(mv.piece.color)
+                    .filter(|(loc, piece)| *loc != mv.from && **piece == mv.piece)
+                    .filter(|(loc, _)| {
+                        self.all_possible_moves()
+                            .iter()
+                            .any(|(l, moves)| l == loc && moves.iter().any(|m| m.to == mv.to))
+                    })
+                    .map(|(loc, _)| loc)
+                    .collect();
+                if !others.is_empty() {
+                    let file = (mv.from.file as u8 + b'a') as char;
+                    let rank = (mv.from.rank as u8 + b'1') as char;
+                    if others.iter().all(|loc| loc.file != mv.from.file) {
+                        san.push(file);
+                    } else if others.iter().all(|loc| loc.rank != mv.from.rank) {
+                        san.push(rank);
+                    } else {
+                        san.push(file);
+                        san.push(rank);
                     }
                 }
+            } else if mv.attack.is_some() {
+                san.push((mv.from.file as u8 + b'a') as char);
+            }
+            if mv.attack.is_some() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+            if let Some(typ) = mv.promote_to {
+                san.push('=');
+                san.push(typ.letter());
             }
         }
-        false
+        let board = self.piece_moved(mv);
+        if board.get_check().is_some() {
+            let mate = board.all_possible_moves().is_empty();
+            san.push(if mate { '#' } else { '+' });
+        }
+        san
     }
 
-    pub fn all_possible_moves(&self) -> Vec<(Location, Vec<Move>)> {
-        self.iter_pieces_of(self.active)
-            .map(|(loc, piece)| (loc, self.possible_moves(loc, *piece, true)))
-            .filter(|(_, moves)| !moves.is_empty())
-            .collect()
+    pub fn parse_uci(&self, uci: &str) -> Option<Move> {
+        let from = uci.get(0..2)?.parse().ok()?;
+        let to = uci.get(2..4)?.parse().ok()?;
+        let promote_to = match uci.get(4..)? {
+            "" => None,
+            "n" => Some(PieceType::Knight),
+            "b" => Some(PieceType::Bishop),
+            "r" => Some(PieceType::Rook),
+            "q" => Some(PieceType::Queen),
+            _ => return None,
+        };
+        self.find_move(from, to, promote_to)
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let san = san.trim();
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.legal_moves();
+
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castle {
+            let mut cands = moves
+                .into_iter()
+                .filter(|mv| mv.castle.is_some() && (mv.to.file > mv.from.file) == kingside);
+            return match (cands.next(), cands.next()) {
+                (Some(mv), None) => Ok(mv),
+                (Some(_), Some(_)) => Err(SanError::Ambiguous),
+                _ => Err(SanError::IllegalMove),
+            };
+        }
+
+        let mut chars: Vec<_> = san.chars().collect();
+        let typ = match chars.first() {
+            Some('N') => Some(PieceType::Knight),
+            Some('B') => Some(PieceType::Bishop),
+            Some('R') => Some(PieceType::Rook),
+            Some('Q') => Some(PieceType::Queen),
+            Some('K') => Some(PieceType::King),
+            Some('a'..='h') => Some(PieceType::Pawn),
+            _ => None,
+        }
+        .ok_or(SanError::Syntax)?;
+        if typ != PieceType::Pawn {
+            chars.remove(0);
+        }
+
+        let mut promote_to = None;
+        if let Some(c) = chars.last().filter(|c| c.is_ascii_alphabetic()) {
+            promote_to = match c.to_ascii_uppercase() {
+                'N' => Some(PieceType::Knight),
+                'B' => Some(PieceType::Bishop),
+                'R' => Some(PieceType::Rook),
+                'Q' => Some(PieceType::Queen),
+                _ => return Err(SanError::Syntax),
+            };
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err(SanError::Syntax);
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to: Location = to.parse().map_err(|_| SanError::Syntax)?;
+        let mut file = None;
+        let mut rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if file.is_none() && rank.is_none() => file = Some(c as i8 - 'a' as i8),
+                '1'..='8' if rank.is_none() => rank = Some(c as i8 - '1' as i8),
+                'x' | ':' | '-' => {}
+                _ => return Err(SanError::Syntax),
+            }
+        }
+
+        let mut cands = moves.into_iter().filter(|mv| {
+            mv.piece.typ == typ
+                && mv.castle.is_none()
+                && mv.to == to
+                && file.map_or(true, |file| mv.from.file == file)
+                && rank.map_or(true, |rank| mv.from.rank == rank)
+                && mv.promote_to == promote_to
+        });
+        match (cands.next(), cands.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous),
+            _ => Err(SanError::IllegalMove),
+        }
     }
 
     pub fn game_state(&self, possible_moves: &[(Location, Vec<Move>)]) -> GameState {
-        if possible_moves.is_empty() {
+        if let Some(outcome) = self.outcome {
+            outcome
+        } else if possible_moves.is_empty() {
             let other = self.active.other();
             if self.can_attack_king(other) {
                 GameState::Checkmate(other)
             } else {
                 GameState::Stalemate
             }
+        } else if self.halfmove_clock >= 100 {
+            GameState::FiftyMoves
+        } else if self.repetitions() >= 3 {
+            GameState::Repetition
+        } else if self.is_insufficient_material() {
+            GameState::InsufficientMaterial
         } else {
             GameState::Normal
         }
@@ -395,158 +1315,166 @@ impl Board {
         st != GameState::Normal
     }
 
-    fn possible_moves(&self, loc: Location, piece: Piece, safe: bool) -> Vec<Move> {
-        let Piece { typ, color } = piece;
-        let mut moves = match typ {
-            PieceType::Pawn => {
-                let mut moves = vec![];
-                let dir = if color.is_white() { 1 } else { -1 };
-                let nloc = loc + (0, dir);
-                if self.get_state(nloc, color) == LocationState::Empty {
-                    moves.push(Move::new(piece, loc, nloc));
+    pub fn conclude(&mut self, outcome: GameState) {
+        self.outcome = Some(outcome);
+    }
 
-                    let second_rank = if color.is_white() { 1 } else { 6 };
-                    if loc.rank == second_rank {
-                        let nloc = nloc + (0, dir);
-                        if self.get_state(nloc, color) == LocationState::Empty {
-                            moves.push(Move::new(piece, loc, nloc));
-                        }
+    fn generate_moves(&self) -> Vec<(Location, Vec<Move>)> {
+        let us = self.active;
+        let them = us.other();
+        let own = self.colors[us.index()];
+        let occupied = self.occupied();
+        let king = self.king_square(us);
+        let (checkers, pinned) = match king {
+            Some(king) => (
+                self.attackers(king, them, occupied, Bitboard::EMPTY),
+                self.pinned(king, us),
+            ),
+            None => (Bitboard::EMPTY, Bitboard::EMPTY),
+        };
+        let evasions = match (king, checkers.lsb()) {
+            (_, None) => !Bitboard::EMPTY,
+            (Some(king), Some(checker)) if checkers.count() == 1 => {
+                checkers | between(king, checker)
+            }
+            _ => Bitboard::EMPTY,
+        };
+
+        let mut all_moves = vec![];
+        for sq in own {
+            let loc = Location::from_square(sq);
+            let piece = self.squares[sq as usize].unwrap();
+            let mut moves = vec![];
+            if piece.is_king() {
+                let occupied = occupied & !Bitboard::square(sq);
+                for to in king_attacks(sq) & !own {
+                    if self
+                        .attackers(to, them, occupied, Bitboard::EMPTY)
+                        .is_empty()
+                    {
+                        moves.push(self.new_move(piece, loc, to));
                     }
                 }
-                for dx in [1, -1] {
-                    let nloc = loc + (dx, dir);
-                    match self.get_state(nloc, color) {
-                        LocationState::Empty => {
-                            if let Some(en_passant) = self.en_passant {
-                                if en_passant == nloc {
-                                    let aloc = nloc + (0, -dir);
-                                    let opponent = Piece::new(PieceType::Pawn, color.other());
-                                    moves.push(
-                                        Move::new(piece, loc, nloc).with_attack(aloc, opponent),
-                                    );
-                                }
-                            }
-                        }
-                        LocationState::Opponent(opponent) => {
-                            moves.push(Move::new(piece, loc, nloc).with_attack(nloc, opponent));
-                        }
-                        _ => {}
-                    }
+                if checkers.is_empty() {
+                    self.castling_moves(loc, piece, &mut moves);
+                }
+            } else {
+                let mut mask = evasions & !own;
+                if let (true, Some(king)) = (pinned.contains(sq), king) {
+                    mask &= line(king, sq);
                 }
-                for mv in &mut moves {
-                    let eighth_rank = if color.is_white() { 7 } else { 0 };
-                    if mv.to.rank == eighth_rank {
-                        mv.is_promotion = true;
+                let targets = match piece.typ {
+                    PieceType::Pawn => {
+                        self.pawn_moves(loc, piece, mask, &mut moves);
+                        Bitboard::EMPTY
                     }
+                    PieceType::Knight => knight_attacks(sq),
+                    PieceType::Bishop => bishop_attacks(sq, occupied),
+                    PieceType::Rook => rook_attacks(sq, occupied),
+                    _ => bishop_attacks(sq, occupied) | rook_attacks(sq, occupied),
+                };
+                for to in targets & mask {
+                    moves.push(self.new_move(piece, loc, to));
                 }
-                moves
-            }
-            PieceType::Knight => {
-                let dirs = all_directions(1, 2);
-                self.moves_to_directions(loc, piece, &dirs, false)
-            }
-            PieceType::Bishop => {
-                let dirs = all_directions(1, 1);
-                self.moves_to_directions(loc, piece, &dirs, true)
             }
-            PieceType::Rook => {
-                let dirs = all_directions(1, 0);
-                self.moves_to_directions(loc, piece, &dirs, true)
+            if !moves.is_empty() {
+                all_moves.push((loc, moves));
             }
-            PieceType::Queen => {
-                let mut dirs = all_directions(1, 1);
-                dirs.extend(all_directions(1, 0));
-                self.moves_to_directions(loc, piece, &dirs, true)
+        }
+        all_moves
+    }
+
+    fn new_move(&self, piece: Piece, from: Location, to: u8) -> Move {
+        let to = Location::from_square(to);
+        let mv = Move::new(piece, from, to);
+        match self.piece(to) {
+            Some(target) => mv.with_attack(to, target),
+            None => mv,
+        }
+    }
+
+    fn pawn_moves(&self, loc: Location, piece: Piece, mask: Bitboard, moves: &mut Vec<Move>) {
+        let color = piece.color;
+        let start = moves.len();
+        let dir = if color.is_white() { 1 } else { -1 };
+        let nloc = loc + (0, dir);
+        if nloc.is_valid() && self.is_empty(nloc) {
+            if mask.contains(nloc.square()) {
+                moves.push(Move::new(piece, loc, nloc));
             }
-            PieceType::King => {
-                let mut dirs = all_directions(1, 1);
-                dirs.extend(all_directions(1, 0));
-                let mut moves = self.moves_to_directions(loc, piece, &dirs, false);
-                let kingside = if color.is_white() {
-                    self.wk_castle
-                } else {
-                    self.bk_castle
-                };
-                if kingside
-                    && self.get_state(loc + (1, 0), color) == LocationState::Empty
-                    && self.get_state(loc + (2, 0), color) == LocationState::Empty
-                {
-                    moves.push(
-                        Move::new(piece, loc, loc + (2, 0)).with_castle(loc + (3, 0), loc + (1, 0)),
-                    );
-                }
-                let queenside = if color.is_white() {
-                    self.wq_castle
-                } else {
-                    self.bq_castle
-                };
-                if queenside
-                    && self.get_state(loc + (-1, 0), color) == LocationState::Empty
-                    && self.get_state(loc + (-2, 0), color) == LocationState::Empty
-                {
-                    moves.push(
-                        Move::new(piece, loc, loc + (-2, 0))
-                            .with_castle(loc + (-4, 0), loc + (-1, 0)),
-                    );
+            let second_rank = if color.is_white() { 1 } else { 6 };
+            if loc.rank == second_rank {
+                let nloc = nloc + (0, dir);
+                if self.is_empty(nloc) && mask.contains(nloc.square()) {
+                    moves.push(Move::new(piece, loc, nloc));
                 }
-                moves
             }
-        };
-        if safe {
-            moves.retain(|mv| {
-                let new_board = self.piece_moved(mv);
-                let opponent = color.other();
-                if new_board.can_attack_king(opponent) {
-                    return false;
-                }
-                if mv.castle.is_some() {
-                    if self.can_attack_king(opponent) {
-                        return false;
-                    }
-                    let dx = mv.to.file - mv.from.file;
-                    let middle_mv = Move::new(mv.piece, mv.from, mv.from + (dx / 2, 0));
-                    let new_board = self.piece_moved(&middle_mv);
-                    if new_board.can_attack_king(opponent) {
-                        return false;
-                    }
+        }
+        let attacks = pawn_attacks(color, loc.square());
+        for to in attacks & self.colors[color.other().index()] & mask {
+            moves.push(self.new_move(piece, loc, to));
+        }
+        if let Some(en_passant) = self.en_passant {
+            let aloc = en_passant + (0, -dir);
+            let opponent = Piece::new(PieceType::Pawn, color.other());
+            if attacks.contains(en_passant.square())
+                && self.is_empty(en_passant)
+                && self.piece(aloc) == Some(opponent)
+            {
+                let mv = Move::new(piece, loc, en_passant).with_attack(aloc, opponent);
+                if !self.king_attacked_after(&mv) {
+                    moves.push(mv);
                 }
-                true
-            });
+            }
+        }
+        let eighth_rank = if color.is_white() { 7 } else { 0 };
+        for mv in &mut moves[start..] {
+            if mv.to.rank == eighth_rank {
+                mv.is_promotion = true;
+            }
         }
-        moves
     }
 
-    fn moves_to_directions(
-        &self,
-        loc: Location,
-        piece: Piece,
-        directions: &[(i8, i8)],
-        repeat: bool,
-    ) -> Vec<Move> {
-        let mut moves = vec![];
-        for dir in directions {
-            let mut nloc = loc;
-            loop {
-                nloc = nloc + dir;
-                match self.get_state(nloc, piece.color) {
-                    LocationState::Empty => {
-                        moves.push(Move::new(piece, loc, nloc));
-                    }
-                    LocationState::Opponent(opponent) => {
-                        moves.push(Move::new(piece, loc, nloc).with_attack(nloc, opponent));
-                        break;
-                    }
-                    _ => break,
-                }
-                if !repeat {
-                    break;
-                }
+    fn castling_moves(&self, loc: Location, piece: Piece, moves: &mut Vec<Move>) {
+        let color = piece.color;
+        let (kingside, queenside) = if color.is_white() {
+            (self.wk_castle, self.wq_castle)
+        } else {
+            (self.bk_castle, self.bq_castle)
+        };
+        for (allowed, dx) in [(kingside, 1), (queenside, -1)] {
+            if !allowed || !self.can_castle(color, dx) {
+                continue;
+            }
+            let middle = loc + (dx, 0);
+            let to = loc + (dx * 2, 0);
+            let rook_from = castle_rook(color, dx);
+            let path = between(loc.square(), rook_from.square());
+            if !(path & self.occupied()).is_empty() {
+                continue;
+            }
+            let mv = Move::new(piece, loc, to).with_castle(rook_from, middle);
+            if !self.king_attacked_after(&Move::new(piece, loc, middle))
+                && !self.king_attacked_after(&mv)
+            {
+                moves.push(mv);
             }
         }
-        moves
     }
 
-    fn update_castle(&mut self, loc: Location, color: Color) {
+    fn can_castle(&self, color: Color, dx: i8) -> bool {
+        self.piece(castle_king(color)) == Some(Piece::new(PieceType::King, color))
+            && self.piece(castle_rook(color, dx)) == Some(Piece::new(PieceType::Rook, color))
+    }
+
+    fn revoke_castles(&mut self) {
+        self.wk_castle &= self.can_castle(Color::White, 1);
+        self.wq_castle &= self.can_castle(Color::White, -1);
+        self.bk_castle &= self.can_castle(Color::Black, 1);
+        self.bq_castle &= self.can_castle(Color::Black, -1);
+    }
+
+    pub fn update_castle(&mut self, loc: Location, color: Color) {
         if color.is_white() {
             if loc.rank == 0 {
                 if loc.file == 0 {
@@ -564,9 +1492,12 @@ impl Board {
         }
     }
 
-    fn piece_moved(&self, mv: &Move) -> Self {
+    pub fn piece_moved(&self, mv: &Move) -> Self {
         let mut new_board = self.clone();
         new_board.en_passant = None;
+        if matches!(self.frozen, Some((_, p)) if p.color == mv.piece.color) {
+            new_board.frozen = None;
+        }
         if let Some((loc, _)) = mv.attack {
             new_board.set_piece(loc, None);
         }
@@ -606,13 +1537,118 @@ impl Board {
         new_board.set_piece(mv.to, Some(piece));
         new_board.last_move = Some(mv.clone());
         new_board.last_event = None;
+        new_board.last_card = None;
+        if mv.piece.is_pawn() || mv.attack.is_some() {
+            new_board.halfmove_clock = 0;
+        } else {
+            new_board.halfmove_clock += 1;
+        }
+        if !mv.piece.color.is_white() {
+            new_board.fullmove_number += 1;
+        }
         new_board.active = new_board.active.other();
-        new_board.half_moves += 1;
+        new_board.plies += 1;
         new_board
     }
 
+    pub(crate) fn clear_history(&mut self) {
+        self.history = vec![];
+    }
+
+    pub(crate) fn rebuild_history(&mut self, previous: &[Board]) {
+        let Some((initial, rest)) = previous.split_first() else {
+            return;
+        };
+        let mut history = initial.history.clone();
+        history.extend(rest.iter().map(Board::position));
+        history.push(self.position());
+        self.history = history;
+    }
+
+    pub(crate) fn without_history(&self) -> Self {
+        let mut board = self.clone();
+        board.history.clear();
+        board
+    }
+
+    pub fn perft(&self, depth: u32) -> u64 {
+        let board = self.without_history();
+        board.count_nodes(depth)
+    }
+
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        let board = self.without_history();
+        board
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let nodes = board.piece_moved(&mv).count_nodes(depth.saturating_sub(1));
+                (mv, nodes)
+            })
+            .collect()
+    }
+
+    fn count_nodes(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mv| self.piece_moved(mv).count_nodes(depth - 1))
+            .sum()
+    }
+
     pub fn move_piece(&mut self, mv: &Move) {
         *self = self.piece_moved(mv);
+        self.history.push(self.position());
+    }
+
+    fn position(&self) -> Position {
+        let en_passant = self.en_passant.filter(|loc| {
+            let dir = if self.active.is_white() { -1 } else { 1 };
+            [-1, 1].into_iter().any(|dx| {
+                let ploc = *loc + (dx, dir);
+                ploc.is_valid()
+                    && self.piece(ploc) == Some(Piece::new(PieceType::Pawn, self.active))
+            })
+        });
+        Position {
+            colors: self.colors,
+            types: self.types,
+            active: self.active,
+            castles: [
+                self.wk_castle,
+                self.wq_castle,
+                self.bk_castle,
+                self.bq_castle,
+            ],
+            en_passant,
+        }
+    }
+
+    fn repetitions(&self) -> usize {
+        let Some(current) = self.history.last() else {
+            return 0;
+        };
+        self.history.iter().filter(|p| *p == current).count()
+    }
+
+    // Cards can turn any piece into mating material, so only a lone minor piece, which no
+    // card can multiply or promote, is treated as insufficient.
+    fn is_insufficient_material(&self) -> bool {
+        let mut minors = 0;
+        for (_, piece) in self.iter_pieces() {
+            match piece.typ {
+                PieceType::King => {}
+                PieceType::Knight | PieceType::Bishop => minors += 1,
+                _ => return false,
+            }
+        }
+        minors <= 1
     }
 
     fn final_rank(&self) -> i8 {
@@ -623,45 +1659,108 @@ impl Board {
         }
     }
 
-    pub fn draw_card(&mut self) {
+    pub fn deal(&mut self, deck: &DeckConfig, rng: &mut impl Rng) {
+        self.white_cards = deck.opening_hand(rng);
+        self.black_cards = deck.opening_hand(rng);
+    }
+
+    pub fn hand(&self, color: Color) -> &[Card] {
+        if color.is_white() {
+            &self.white_cards
+        } else {
+            &self.black_cards
+        }
+    }
+
+    pub fn draw_card(&mut self, deck: &DeckConfig, rng: &mut impl Rng) {
+        self.play_card(self.hand(self.active).len() - 1, deck, rng);
+    }
+
+    pub fn play_card(&mut self, index: usize, deck: &DeckConfig, rng: &mut impl Rng) -> bool {
+        let Some(card) = self.take_card(index, deck, rng) else {
+            return false;
+        };
+        let ev = self.event_candidates(card).choose(rng).copied();
+        if let Some(ev) = ev {
+            self.apply_event(ev);
+        }
+        true
+    }
+
+    pub fn take_card(
+        &mut self,
+        index: usize,
+        deck: &DeckConfig,
+        rng: &mut impl Rng,
+    ) -> Option<Card> {
         let cards = if self.active.is_white() {
             &mut self.white_cards
         } else {
             &mut self.black_cards
         };
-        let card = cards.pop().unwrap();
+        if index >= cards.len() {
+            return None;
+        }
+        let card = cards.remove(index);
         self.last_card = Some(card);
-        if cards.is_empty() {
-            *cards = generate_cards(5);
-        }
-        let ev = match card {
-            0 => None,
-            1 => self.make_b2n(),
-            2 => self.make_n2b(),
-            3 => self.make_r2q(),
-            4 => self.make_q2r(),
-            5 => self.make_p2q(),
-            6 => self.make_q2p(),
-            7 => self.make_swap(),
-            8 => self.make_rotate(),
-            9 => self.make_pawn_run(),
-            _ => self.make_king_move(),
-        };
-        if let Some(ev) = ev {
-            self.apply_event(ev);
+        deck.refill(cards, rng);
+        Some(card)
+    }
+
+    pub fn event_candidates(&self, card: Card) -> Vec<Event> {
+        match (card.entry(), card.effect()) {
+            (Some(entry), _) => (entry.generate)(self),
+            (_, Some(effect)) => effect
+                .candidates(self)
+                .into_iter()
+                .filter(|ev| ev.card() == card)
+                .map(Event::Custom)
+                .filter(|ev| self.is_valid_event(*ev))
+                .collect(),
+            _ => vec![],
         }
     }
 
-    fn gen_events<F: FnOnce(&[(Location, &Piece)], &mut Vec<Event>)>(&self, f: F) -> Option<Event> {
-        let pieces: Vec<_> = self.iter_active_pieces().collect();
+    pub fn replay_card(&mut self, card: Card, event: Option<Event>) -> bool {
+        let cands = self.event_candidates(card);
+        match event {
+            Some(ev) if cands.contains(&ev) => {
+                self.last_card = Some(card);
+                self.apply_event(ev);
+                true
+            }
+            None if cands.is_empty() => {
+                self.last_card = Some(card);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn gen_events<F: FnOnce(&[(Location, &Piece)], &mut Vec<Event>)>(&self, f: F) -> Vec<Event> {
+        self.gen_events_of(self.active, f)
+    }
+
+    fn gen_enemy_events<F: FnOnce(&[(Location, &Piece)], &mut Vec<Event>)>(
+        &self,
+        f: F,
+    ) -> Vec<Event> {
+        self.gen_events_of(self.active.other(), f)
+    }
+
+    fn gen_events_of<F: FnOnce(&[(Location, &Piece)], &mut Vec<Event>)>(
+        &self,
+        color: Color,
+        f: F,
+    ) -> Vec<Event> {
+        let pieces: Vec<_> = self.iter_pieces_of(color).collect();
         let mut cands = vec![];
         f(&pieces, &mut cands);
         cands.retain(|e| self.is_valid_event(*e));
-        cands.shuffle(&mut thread_rng());
-        cands.into_iter().next()
+        cands
     }
 
-    fn make_swap(&self) -> Option<Event> {
+    fn make_swap(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             for (i, (l1, p1)) in pieces.iter().enumerate() {
                 for (l2, p2) in pieces.iter().take(i) {
@@ -680,7 +1779,7 @@ impl Board {
         })
     }
 
-    fn make_n2b(&self) -> Option<Event> {
+    fn make_n2b(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             for (l, _) in pieces.iter().filter(|(_, p)| p.is_knight()) {
                 cands.push(Event::KnightToBishop(*l));
@@ -688,7 +1787,7 @@ impl Board {
         })
     }
 
-    fn make_b2n(&self) -> Option<Event> {
+    fn make_b2n(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             for (l, _) in pieces.iter().filter(|(_, p)| p.is_bishop()) {
                 cands.push(Event::BishopToKnight(*l));
@@ -696,7 +1795,7 @@ impl Board {
         })
     }
 
-    fn make_r2q(&self) -> Option<Event> {
+    fn make_r2q(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             let rooks: Vec<_> = pieces.iter().filter(|(_, p)| p.is_rook()).collect();
             for (l1, _) in rooks.iter() {
@@ -709,7 +1808,7 @@ impl Board {
         })
     }
 
-    fn make_q2r(&self) -> Option<Event> {
+    fn make_q2r(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             let emptys: Vec<_> = self.iter_empty_locations().collect();
             for (l1, _) in pieces.iter().filter(|(_, p)| p.is_queen()) {
@@ -720,7 +1819,7 @@ impl Board {
         })
     }
 
-    fn make_pawn_run(&self) -> Option<Event> {
+    fn make_pawn_run(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             let dy = if self.active.is_white() { 1 } else { -1 };
             for (l, _) in pieces.iter().filter(|(_, p)| p.is_pawn()) {
@@ -739,7 +1838,7 @@ impl Board {
         })
     }
 
-    fn make_p2q(&self) -> Option<Event> {
+    fn make_p2q(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             let pawns: Vec<_> = pieces
                 .iter()
@@ -757,7 +1856,7 @@ impl Board {
         })
     }
 
-    fn make_q2p(&self) -> Option<Event> {
+    fn make_q2p(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             for (l, _) in pieces.iter().filter(|(_, p)| p.is_queen()) {
                 for rank in 0..8 {
@@ -780,7 +1879,7 @@ impl Board {
         })
     }
 
-    fn make_rotate(&self) -> Option<Event> {
+    fn make_rotate(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             for (l, _) in pieces.iter() {
                 match l.file {
@@ -802,7 +1901,7 @@ impl Board {
         })
     }
 
-    fn make_king_move(&self) -> Option<Event> {
+    fn make_king_move(&self) -> Vec<Event> {
         self.gen_events(|pieces, cands| {
             let (l, _) = pieces.iter().find(|(_, p)| p.is_king()).unwrap();
             let dx = if l.file < 4 { 1 } else { -1 };
@@ -820,20 +1919,52 @@ impl Board {
         })
     }
 
+    fn make_demote_queen(&self) -> Vec<Event> {
+        self.gen_enemy_events(|pieces, cands| {
+            for (l, _) in pieces.iter().filter(|(_, p)| p.is_queen()) {
+                cands.push(Event::DemoteQueen(*l));
+            }
+        })
+    }
+
+    fn make_freeze(&self) -> Vec<Event> {
+        self.gen_enemy_events(|pieces, cands| {
+            for (l, _) in pieces.iter().filter(|(_, p)| !p.is_king()) {
+                cands.push(Event::Freeze(*l));
+            }
+        })
+    }
+
+    fn make_push_back(&self) -> Vec<Event> {
+        self.gen_enemy_events(|pieces, cands| {
+            let dy = if self.active.is_white() { 1 } else { -1 };
+            for (l, _) in pieces.iter().filter(|(_, p)| p.is_pawn()) {
+                let nloc = *l + (0, dy);
+                if (1..7).contains(&nloc.rank) && self.is_empty(nloc) {
+                    cands.push(Event::PushBack(*l, nloc));
+                }
+            }
+        })
+    }
+
     fn is_valid_event(&self, event: Event) -> bool {
-        let board = self.event_applied(event);
+        let board = self.without_history().event_applied(event);
         !board.can_attack_king(board.active) && !board.all_possible_moves().is_empty()
     }
 
-    fn event_applied(&self, event: Event) -> Self {
+    pub fn event_applied(&self, event: Event) -> Self {
         let mut new_board = self.clone();
         match event {
             Event::Swap(l1, l2) => {
                 let p1 = self.piece(l1);
-                new_board.set_piece(l1, self.piece(l2));
+                let p2 = self.piece(l2);
+                new_board.set_piece(l1, p2);
                 new_board.set_piece(l2, p1);
                 new_board.update_castle(l1, new_board.active);
                 new_board.update_castle(l2, new_board.active);
+                if p1.unwrap().is_pawn() || p2.unwrap().is_pawn() {
+                    new_board.halfmove_clock = 0;
+                }
             }
             Event::KnightToBishop(l) => {
                 let mut piece = self.piece(l).unwrap();
@@ -868,6 +1999,7 @@ impl Board {
                 let piece = self.piece(l1).unwrap();
                 new_board.set_piece(l1, None);
                 new_board.set_piece(l2, Some(piece));
+                new_board.halfmove_clock = 0;
             }
             Event::PawnsToQueen(ls) => {
                 let mut piece = self.piece(ls[0]).unwrap();
@@ -876,6 +2008,7 @@ impl Board {
                 for l in ls.iter().skip(1) {
                     new_board.set_piece(*l, None);
                 }
+                new_board.halfmove_clock = 0;
             }
             Event::QueenToPawns(l, rank) => {
                 let mut piece = self.piece(l).unwrap();
@@ -885,12 +2018,16 @@ impl Board {
                     let loc = Location::new(i as _, rank);
                     new_board.set_piece(loc, Some(piece));
                 }
+                new_board.halfmove_clock = 0;
             }
             Event::Rotate(l1, l2) => {
                 let piece = self.piece(l1).unwrap();
                 new_board.set_piece(l1, None);
                 new_board.set_piece(l2, Some(piece));
                 new_board.update_castle(l1, new_board.active);
+                if piece.is_pawn() {
+                    new_board.halfmove_clock = 0;
+                }
             }
             Event::KingMove(l1, l2) => {
                 let piece = self.piece(l1).unwrap();
@@ -904,56 +2041,58 @@ impl Board {
                     new_board.bq_castle = false;
                 }
             }
+            Event::DemoteQueen(l) => {
+                let mut piece = self.piece(l).unwrap();
+                piece.typ = PieceType::Rook;
+                new_board.set_piece(l, Some(piece));
+            }
+            Event::Freeze(l) => {
+                new_board.frozen = Some((l, self.piece(l).unwrap()));
+            }
+            Event::PushBack(l1, l2) => {
+                let piece = self.piece(l1).unwrap();
+                new_board.set_piece(l1, None);
+                new_board.set_piece(l2, Some(piece));
+                new_board.en_passant = None;
+                new_board.halfmove_clock = 0;
+            }
+            Event::Custom(ev) => {
+                if let Some(effect) = ev.card().effect() {
+                    effect.apply(&mut new_board, &ev);
+                }
+            }
         }
+        new_board.revoke_castles();
         new_board.last_event = Some(event);
         new_board
     }
 
     fn apply_event(&mut self, event: Event) {
         *self = self.event_applied(event);
+        let position = self.position();
+        if let Some(last) = self.history.last_mut() {
+            *last = position;
+        }
     }
 }
 
-fn all_directions(dfile: i8, drank: i8) -> Vec<(i8, i8)> {
-    let mut dirs = vec![];
-    let swap = dfile != drank;
-    dirs.push((dfile, drank));
-    if swap {
-        dirs.push((drank, dfile));
-    }
-    if dfile != 0 {
-        dirs.push((-dfile, drank));
-        if swap {
-            dirs.push((drank, -dfile));
-        }
-    }
-    if drank != 0 {
-        dirs.push((dfile, -drank));
-        if swap {
-            dirs.push((-drank, dfile));
-        }
-    }
-    if dfile != 0 && drank != 0 {
-        dirs.push((-dfile, -drank));
-        if swap {
-            dirs.push((-drank, -dfile));
-        }
-    }
-    dirs
+fn castle_king(color: Color) -> Location {
+    let rank = if color.is_white() { 0 } else { 7 };
+    Location::new(4, rank)
 }
 
-fn generate_cards(len: usize) -> Vec<usize> {
-    (0..len)
-        .map(|_| {
-            if thread_rng().gen_bool(0.5) {
-                0
-            } else {
-                thread_rng().gen_range(1..=10)
-            }
-        })
-        .collect()
+fn castle_rook(color: Color, dx: i8) -> Location {
+    let king = castle_king(color);
+    Location::new(if dx > 0 { 7 } else { 0 }, king.rank)
 }
 
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
 const INIT_PIECES: [[Option<Piece>; 8]; 8] = [
     [
         Some(Piece::new(PieceType::Rook, Color::White)),
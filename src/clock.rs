@@ -0,0 +1,104 @@
+use crate::chess::Color;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    Fischer(Duration),
+    Bronstein(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub bonus: Option<Bonus>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ClockState {
+    white: u64,
+    black: u64,
+    running: Option<Color>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    white: Duration,
+    black: Duration,
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            white: control.base,
+            black: control.base,
+            running: None,
+        }
+    }
+
+    fn stored(&mut self, color: Color) -> &mut Duration {
+        if color.is_white() {
+            &mut self.white
+        } else {
+            &mut self.black
+        }
+    }
+
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let stored = if color.is_white() {
+            self.white
+        } else {
+            self.black
+        };
+        match self.running {
+            Some((c, start)) if c == color => stored.saturating_sub(now - start),
+            _ => stored,
+        }
+    }
+
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        let (color, _) = self.running?;
+        if self.remaining(color, now).is_zero() {
+            Some(color)
+        } else {
+            None
+        }
+    }
+
+    pub fn punch(&mut self, color: Color, now: Instant) {
+        if let Some((c, start)) = self.running {
+            if c == color {
+                let elapsed = now - start;
+                let bonus = self.control.bonus;
+                let stored = self.stored(color);
+                *stored = match bonus {
+                    Some(Bonus::Fischer(inc)) => stored.saturating_sub(elapsed).saturating_add(inc),
+                    Some(Bonus::Bronstein(delay)) => {
+                        stored.saturating_sub(elapsed.saturating_sub(delay))
+                    }
+                    None => stored.saturating_sub(elapsed),
+                };
+            }
+        }
+        self.running = Some((color.other(), now));
+    }
+
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((color, _)) = self.running {
+            let remaining = self.remaining(color, now);
+            *self.stored(color) = remaining;
+        }
+        self.running = None;
+    }
+
+    pub fn state(&self, now: Instant) -> ClockState {
+        ClockState {
+            white: self.remaining(Color::White, now).as_millis() as u64,
+            black: self.remaining(Color::Black, now).as_millis() as u64,
+            running: self.running.map(|(color, _)| color),
+        }
+    }
+}
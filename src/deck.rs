@@ -0,0 +1,108 @@
+use crate::chess::Card;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Refill {
+    WhenEmpty,
+    Continuous,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DeckConfig {
+    pub name: String,
+    pub weights: BTreeMap<Card, u32>,
+    pub hand_size: usize,
+    pub refill: Refill,
+    pub opening_hand: Vec<Card>,
+}
+
+impl Default for DeckConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl DeckConfig {
+    pub const PRESETS: [&'static str; 3] = ["calm", "standard", "mayhem"];
+    pub const MAX_HAND_SIZE: usize = 16;
+    pub const MAX_TOTAL_WEIGHT: u64 = 1_000_000;
+
+    fn with_blank(name: &str, blank: u32) -> Self {
+        let weights = Card::builtin()
+            .map(|card| (card, if card == Card::Blank { blank } else { 1 }))
+            .collect();
+        Self {
+            name: name.to_string(),
+            weights,
+            hand_size: 5,
+            refill: Refill::WhenEmpty,
+            opening_hand: vec![Card::Blank, Card::Blank],
+        }
+    }
+
+    pub fn calm() -> Self {
+        Self::with_blank("calm", 30)
+    }
+
+    pub fn standard() -> Self {
+        let blank = Card::builtin().filter(|card| *card != Card::Blank).count() as u32;
+        Self::with_blank("standard", blank)
+    }
+
+    pub fn mayhem() -> Self {
+        Self {
+            hand_size: 3,
+            refill: Refill::Continuous,
+            opening_hand: vec![],
+            ..Self::with_blank("mayhem", 0)
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "calm" => Some(Self::calm()),
+            "standard" => Some(Self::standard()),
+            "mayhem" => Some(Self::mayhem()),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let total: u64 = self.weights.values().map(|w| *w as u64).sum();
+        (1..=Self::MAX_HAND_SIZE).contains(&self.hand_size)
+            && self.opening_hand.len() <= Self::MAX_HAND_SIZE
+            && (1..=Self::MAX_TOTAL_WEIGHT).contains(&total)
+    }
+
+    pub fn generate(&self, len: usize, rng: &mut impl Rng) -> Vec<Card> {
+        let weights: Vec<_> = self.weights.iter().collect();
+        (0..len)
+            .map(|_| *weights.choose_weighted(rng, |(_, w)| **w).unwrap().0)
+            .collect()
+    }
+
+    pub fn opening_hand(&self, rng: &mut impl Rng) -> Vec<Card> {
+        if self.opening_hand.is_empty() {
+            self.generate(self.hand_size, rng)
+        } else {
+            self.opening_hand.clone()
+        }
+    }
+
+    pub fn refill(&self, cards: &mut Vec<Card>, rng: &mut impl Rng) {
+        match self.refill {
+            Refill::WhenEmpty => {
+                if cards.is_empty() {
+                    *cards = self.generate(self.hand_size, rng);
+                }
+            }
+            Refill::Continuous => {
+                let missing = self.hand_size.saturating_sub(cards.len());
+                cards.splice(0..0, self.generate(missing, rng));
+            }
+        }
+    }
+}
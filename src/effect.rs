@@ -0,0 +1,131 @@
+use crate::chess::{Board, Card, Location};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+const MAX_LOCATIONS: usize = 8;
+
+pub trait CardEffect: Send + Sync {
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    fn candidates(&self, board: &Board) -> Vec<CustomEvent>;
+
+    fn apply(&self, board: &mut Board, event: &CustomEvent);
+
+    fn describe(&self, event: &CustomEvent) -> String {
+        format!("{}: {}", self.name(), self.serialize(event))
+    }
+
+    fn serialize(&self, event: &CustomEvent) -> String {
+        let locs: Vec<_> = event.locations().iter().map(Location::to_string).collect();
+        locs.join(" ")
+    }
+
+    fn deserialize(&self, args: &[&str]) -> Option<CustomEvent> {
+        let locs = args
+            .iter()
+            .map(|arg| arg.parse().ok())
+            .collect::<Option<Vec<_>>>()?;
+        self.event(&locs)
+    }
+
+    fn event(&self, locations: &[Location]) -> Option<CustomEvent> {
+        CustomEvent::new(Card::Custom(self.id()), locations)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(into = "CustomEventData", try_from = "CustomEventData")]
+pub struct CustomEvent {
+    card: Card,
+    len: usize,
+    locations: [Location; MAX_LOCATIONS],
+}
+
+#[derive(Deserialize, Serialize)]
+struct CustomEventData {
+    card: Card,
+    locations: Vec<Location>,
+}
+
+impl From<CustomEvent> for CustomEventData {
+    fn from(event: CustomEvent) -> Self {
+        Self {
+            card: event.card,
+            locations: event.locations().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<CustomEventData> for CustomEvent {
+    type Error = &'static str;
+
+    fn try_from(data: CustomEventData) -> Result<Self, Self::Error> {
+        Self::new(data.card, &data.locations).ok_or("invalid custom event")
+    }
+}
+
+impl CustomEvent {
+    pub fn new(card: Card, locations: &[Location]) -> Option<Self> {
+        if !matches!(card, Card::Custom(_)) || locations.len() > MAX_LOCATIONS {
+            return None;
+        }
+        let mut locs = [Location::new(0, 0); MAX_LOCATIONS];
+        locs[..locations.len()].copy_from_slice(locations);
+        Some(Self {
+            card,
+            len: locations.len(),
+            locations: locs,
+        })
+    }
+
+    #[inline]
+    pub fn card(&self) -> Card {
+        self.card
+    }
+
+    #[inline]
+    pub fn locations(&self) -> &[Location] {
+        &self.locations[..self.len]
+    }
+}
+
+static EFFECTS: RwLock<Vec<&'static dyn CardEffect>> = RwLock::new(Vec::new());
+
+pub fn register(effect: impl CardEffect + 'static) -> Card {
+    let id = effect.id();
+    assert!(
+        !id.is_empty() && !id.contains(char::is_whitespace),
+        "invalid card id {:?}",
+        id
+    );
+    assert!(
+        Card::builtin().all(|card| card.to_string() != id),
+        "card id {:?} is already taken",
+        id
+    );
+    let mut effects = EFFECTS.write().unwrap();
+    assert!(
+        effects.iter().all(|effect| effect.id() != id),
+        "card id {:?} is already taken",
+        id
+    );
+    effects.push(Box::leak(Box::new(effect)));
+    Card::Custom(id)
+}
+
+pub(crate) fn lookup(id: &str) -> Option<&'static dyn CardEffect> {
+    let effects = EFFECTS.read().unwrap();
+    effects.iter().find(|effect| effect.id() == id).copied()
+}
+
+pub(crate) fn registered() -> Vec<Card> {
+    let effects = EFFECTS.read().unwrap();
+    effects
+        .iter()
+        .map(|effect| Card::Custom(effect.id()))
+        .collect()
+}
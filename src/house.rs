@@ -0,0 +1,42 @@
+use crate::{chess::*, effect::*};
+
+pub struct PawnRetreat;
+
+impl CardEffect for PawnRetreat {
+    fn id(&self) -> &'static str {
+        "PawnRetreat"
+    }
+
+    fn name(&self) -> &'static str {
+        "후퇴"
+    }
+
+    fn description(&self) -> &'static str {
+        "폰 하나가 한 칸 뒤로 물러납니다."
+    }
+
+    fn candidates(&self, board: &Board) -> Vec<CustomEvent> {
+        let back = if board.active.is_white() { -1 } else { 1 };
+        board
+            .iter_active_pieces()
+            .filter(|(_, piece)| piece.is_pawn())
+            .filter_map(|(loc, _)| {
+                let to = loc + (0, back);
+                if !(1..7).contains(&to.rank()) || !board.is_empty(to) {
+                    return None;
+                }
+                self.event(&[loc, to])
+            })
+            .collect()
+    }
+
+    fn apply(&self, board: &mut Board, event: &CustomEvent) {
+        let &[from, to] = event.locations() else {
+            return;
+        };
+        let piece = board.piece(from);
+        board.set_piece(from, None);
+        board.set_piece(to, piece);
+        board.halfmove_clock = 0;
+    }
+}
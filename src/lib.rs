@@ -1,3 +1,12 @@
+pub mod ai;
+pub mod bitboard;
 pub mod chess;
+pub mod clock;
+pub mod deck;
+pub mod effect;
+pub mod house;
+pub mod lobby;
+pub mod pgn;
+pub mod record;
 pub mod server;
 pub mod session;
@@ -0,0 +1,123 @@
+use crate::server::{Config, Server};
+use actix::*;
+use rand::{distributions::Alphanumeric, *};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const ROOM_ID_LEN: usize = 6;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: String,
+    pub white: bool,
+    pub black: bool,
+    pub spectators: usize,
+    pub deck: String,
+}
+
+#[derive(Message)]
+#[rtype(String)]
+pub struct CreateRoom {
+    pub config: Config,
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<RoomInfo>")]
+pub struct ListRooms;
+
+#[derive(Message)]
+#[rtype(result = "Option<Addr<Server>>")]
+pub struct GetRoom {
+    pub id: String,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UpdateRoom {
+    pub info: RoomInfo,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RemoveRoom {
+    pub id: String,
+}
+
+#[derive(Default)]
+pub struct Lobby {
+    rooms: HashMap<String, (Addr<Server>, RoomInfo)>,
+}
+
+impl Lobby {
+    fn new_id(&self) -> String {
+        loop {
+            let id: String = thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(ROOM_ID_LEN)
+                .map(char::from)
+                .collect();
+            if !self.rooms.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+impl Actor for Lobby {
+    type Context = Context<Self>;
+}
+
+impl Handler<CreateRoom> for Lobby {
+    type Result = String;
+
+    fn handle(&mut self, msg: CreateRoom, ctx: &mut Context<Self>) -> Self::Result {
+        let id = self.new_id();
+        println!("created room {}", id);
+        let info = RoomInfo {
+            id: id.clone(),
+            deck: msg.config.deck.name.clone(),
+            ..RoomInfo::default()
+        };
+        let addr = Server::new(id.clone(), ctx.address(), msg.config).start();
+        self.rooms.insert(id.clone(), (addr, info));
+        id
+    }
+}
+
+impl Handler<ListRooms> for Lobby {
+    type Result = Vec<RoomInfo>;
+
+    fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
+        let mut rooms: Vec<_> = self.rooms.values().map(|(_, info)| info.clone()).collect();
+        rooms.sort_by(|r1, r2| r1.id.cmp(&r2.id));
+        rooms
+    }
+}
+
+impl Handler<GetRoom> for Lobby {
+    type Result = Option<Addr<Server>>;
+
+    fn handle(&mut self, msg: GetRoom, _: &mut Context<Self>) -> Self::Result {
+        let (addr, _) = self.rooms.get(&msg.id)?;
+        Some(addr.clone())
+    }
+}
+
+impl Handler<UpdateRoom> for Lobby {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateRoom, _: &mut Context<Self>) {
+        if let Some((_, info)) = self.rooms.get_mut(&msg.info.id) {
+            *info = msg.info;
+        }
+    }
+}
+
+impl Handler<RemoveRoom> for Lobby {
+    type Result = ();
+
+    fn handle(&mut self, msg: RemoveRoom, _: &mut Context<Self>) {
+        println!("removed room {}", msg.id);
+        self.rooms.remove(&msg.id);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::{chess::*, deck::*, record::*};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    Tag(String),
+    Fen(FenError),
+    Syntax(String),
+    IllegalMove(usize, String),
+    InvalidCard(usize, String),
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Tag(line) => write!(f, "malformed tag pair: {}", line),
+            PgnError::Fen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnError::Syntax(token) => write!(f, "unexpected token: {}", token),
+            PgnError::IllegalMove(ply, san) => write!(f, "illegal move at ply {}: {}", ply, san),
+            PgnError::InvalidCard(ply, card) => {
+                write!(f, "invalid card at ply {}: {}", ply, card)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<FenError> for PgnError {
+    fn from(err: FenError) -> Self {
+        PgnError::Fen(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Headers {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for Headers {
+    fn default() -> Self {
+        Self {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: today(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+pub fn result(state: GameState) -> &'static str {
+    match state {
+        GameState::Normal => "*",
+        GameState::Checkmate(color) => {
+            if color.is_white() {
+                "1-0"
+            } else {
+                "0-1"
+            }
+        }
+        GameState::Resigned(color) | GameState::Timeout(color) => {
+            if color.is_white() {
+                "0-1"
+            } else {
+                "1-0"
+            }
+        }
+        GameState::Stalemate
+        | GameState::FiftyMoves
+        | GameState::Repetition
+        | GameState::InsufficientMaterial
+        | GameState::DrawAgreed => "1/2-1/2",
+    }
+}
+
+fn termination(state: GameState) -> &'static str {
+    match state {
+        GameState::Normal => "unterminated",
+        GameState::Timeout(_) => "time forfeit",
+        _ => "normal",
+    }
+}
+
+pub fn export(record: &GameRecord, headers: &Headers) -> String {
+    let board = record.board();
+    let state = board.game_state(&board.all_possible_moves());
+    let result = result(state);
+
+    let mut tags = vec![
+        ("Event", headers.event.clone()),
+        ("Site", headers.site.clone()),
+        ("Date", headers.date.clone()),
+        ("Round", "-".to_string()),
+        ("White", headers.white.clone()),
+        ("Black", headers.black.clone()),
+        ("Result", result.to_string()),
+    ];
+    let initial = record.initial().to_string();
+    if initial != Board::default().to_string() {
+        tags.push(("SetUp", "1".to_string()));
+        tags.push(("FEN", initial));
+    }
+    tags.push(("Termination", termination(state).to_string()));
+    if let Some(deck) = record.deck() {
+        let value = match DeckConfig::preset(&deck.name) {
+            Some(preset) if preset == *deck => deck.name.clone(),
+            _ => serde_json::to_string(deck).unwrap(),
+        };
+        tags.push(("Deck", value));
+    }
+    if let Some(seed) = record.seed() {
+        tags.push(("Seed", seed.to_string()));
+    }
+
+    let mut pgn = String::new();
+    for (name, value) in tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+    pgn.push('\n');
+
+    let mut tokens = vec![];
+    let mut numbered = false;
+    for (board, ply) in record.history().iter().zip(record.plies()) {
+        if board.active.is_white() {
+            tokens.push(format!("{}.", board.fullmove_number));
+        } else if !numbered {
+            tokens.push(format!("{}...", board.fullmove_number));
+        }
+        tokens.push(board.san(&ply.mv));
+        numbered = true;
+        if let Some(card) = ply.card {
+            let comment = match ply.event {
+                Some(event) => format!("{{[%card {}]}}", event),
+                None => format!("{{[%card {}]}}", card),
+            };
+            tokens.push(comment);
+            numbered = false;
+        }
+    }
+    tokens.push(result.to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Symbol(String),
+    Comment(String),
+}
+
+fn tokenize(movetext: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = vec![];
+    let mut chars = movetext.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                tokens.push(Token::Comment(comment));
+            }
+            ';' => {
+                chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+            }
+            '(' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        Some('{') => chars.by_ref().take_while(|c| *c != '}').for_each(drop),
+                        Some(_) => {}
+                        None => return Err(PgnError::Syntax("(".to_string())),
+                    }
+                }
+            }
+            ')' | '}' => return Err(PgnError::Syntax(c.to_string())),
+            '$' => while chars.next_if(|c| c.is_ascii_digit()).is_some() {},
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{};()$".contains(*c))
+                {
+                    symbol.push(c);
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let err = || PgnError::Tag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(err)?;
+    let (name, value) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(err)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(err)?;
+    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+    Ok((name.to_string(), value))
+}
+
+fn strip_move_number(symbol: &str) -> &str {
+    let rest = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == symbol.len() || !rest.starts_with('.') {
+        return symbol;
+    }
+    rest.trim_start_matches('.')
+}
+
+fn replay_card(board: &mut Board, annotation: &str) -> bool {
+    let mut words = annotation.splitn(2, char::is_whitespace);
+    let Some(Ok(card)) = words.next().map(str::parse) else {
+        return false;
+    };
+    let event = match words.next().map(str::trim) {
+        Some(args) if !args.is_empty() => match annotation.parse() {
+            Ok(ev) => Some(ev),
+            Err(_) => return false,
+        },
+        _ => None,
+    };
+    board.replay_card(card, event)
+}
+
+pub fn import(pgn: &str) -> Result<GameRecord, PgnError> {
+    let mut tags = vec![];
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('%') {
+            continue;
+        }
+        if movetext.trim().is_empty() && line.starts_with('[') {
+            tags.push(parse_tag(line)?);
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let board = match tag("FEN") {
+        Some(fen) => Board::from_fen(fen)?,
+        None => Board::default(),
+    };
+    let mut record = GameRecord::new(board);
+    if let Some(deck) = tag("Deck") {
+        let config = DeckConfig::preset(deck)
+            .or_else(|| serde_json::from_str::<DeckConfig>(deck).ok())
+            .filter(DeckConfig::is_valid)
+            .ok_or_else(|| PgnError::Tag(deck.to_string()))?;
+        record.set_deck(config);
+    }
+    if let Some(seed) = tag("Seed") {
+        let seed = seed.parse().map_err(|_| PgnError::Tag(seed.to_string()))?;
+        record.set_seed(seed);
+    }
+    let mut result = None;
+    for token in tokenize(&movetext)? {
+        match token {
+            Token::Comment(comment) => {
+                let Some((_, rest)) = comment.split_once("[%card") else {
+                    continue;
+                };
+                let annotation = rest.split(']').next().unwrap_or_default().trim();
+                let ply = record.cursor();
+                if ply == 0 || !replay_card(record.board_mut(), annotation) {
+                    return Err(PgnError::InvalidCard(ply, annotation.to_string()));
+                }
+            }
+            Token::Symbol(symbol) => {
+                if result.is_some() {
+                    return Err(PgnError::Syntax(symbol));
+                }
+                if ["1-0", "0-1", "1/2-1/2", "*"].contains(&symbol.as_str()) {
+                    result = Some(symbol);
+                    continue;
+                }
+                let san = strip_move_number(&symbol);
+                if san.is_empty() {
+                    continue;
+                }
+                let mut board = record.board().clone();
+                let ply = record.cursor() + 1;
+                let mv = board
+                    .parse_san(san)
+                    .map_err(|_| PgnError::IllegalMove(ply, san.to_string()))?;
+                board.move_piece(&mv);
+                record.push(board);
+            }
+        }
+    }
+
+    let board = record.board_mut();
+    if !board.is_game_over() {
+        let time_forfeit = tag("Termination") == Some("time forfeit");
+        let outcome = match result.as_deref().or(tag("Result")) {
+            Some("1/2-1/2") => Some(GameState::DrawAgreed),
+            Some("1-0") if time_forfeit => Some(GameState::Timeout(Color::Black)),
+            Some("0-1") if time_forfeit => Some(GameState::Timeout(Color::White)),
+            Some("1-0") => Some(GameState::Resigned(Color::Black)),
+            Some("0-1") => Some(GameState::Resigned(Color::White)),
+            _ => None,
+        };
+        if let Some(outcome) = outcome {
+            board.conclude(outcome);
+        }
+    }
+    Ok(record)
+}
+
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,158 @@
+use crate::{chess::*, deck::*};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ply {
+    pub mv: Move,
+    pub card: Option<Card>,
+    pub event: Option<Event>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    boards: Vec<Board>,
+    cursor: usize,
+    seed: Option<u64>,
+    deck: Option<DeckConfig>,
+}
+
+impl Default for GameRecord {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Board::default())
+    }
+}
+
+impl GameRecord {
+    pub fn new(board: Board) -> Self {
+        Self {
+            boards: vec![board],
+            cursor: 0,
+            seed: None,
+            deck: None,
+        }
+    }
+
+    #[inline]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    pub fn clear_seed(&mut self) {
+        self.seed = None;
+    }
+
+    #[inline]
+    pub fn deck(&self) -> Option<&DeckConfig> {
+        self.deck.as_ref()
+    }
+
+    pub fn set_deck(&mut self, deck: DeckConfig) {
+        self.deck = Some(deck);
+    }
+
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.boards[self.cursor]
+    }
+
+    #[inline]
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.boards[self.cursor]
+    }
+
+    #[inline]
+    pub fn initial(&self) -> &Board {
+        &self.boards[0]
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn history(&self) -> &[Board] {
+        &self.boards[..self.cursor]
+    }
+
+    pub fn plies(&self) -> Vec<Ply> {
+        self.boards[1..=self.cursor]
+            .iter()
+            .map(|board| Ply {
+                mv: board.last_move.clone().unwrap(),
+                card: board.last_card,
+                event: board.last_event,
+            })
+            .collect()
+    }
+
+    // Only the current board keeps its repetition history; the others are rebuilt from their
+    // positions when the cursor moves back to them.
+    fn detach(&mut self) {
+        if self.cursor > 0 {
+            self.boards[self.cursor].clear_history();
+        }
+    }
+
+    pub fn push(&mut self, board: Board) {
+        assert!(board.last_move.is_some());
+        self.detach();
+        self.boards.truncate(self.cursor + 1);
+        self.boards.push(board);
+        self.cursor += 1;
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.goto(self.cursor.wrapping_sub(1))
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.goto(self.cursor + 1)
+    }
+
+    pub fn goto(&mut self, ply: usize) -> bool {
+        if ply < self.boards.len() {
+            self.detach();
+            self.cursor = ply;
+            if ply > 0 {
+                let (previous, rest) = self.boards.split_at_mut(ply);
+                rest[0].rebuild_history(previous);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    // Deals the hands from `rng` and plays every recorded card again from the end of the hand, as
+    // automatic play does. Fails without touching the record if a card was not in hand or drew a
+    // different event.
+    pub fn redeal(&mut self, deck: &DeckConfig, rng: &mut impl Rng) -> bool {
+        let mut board = self.boards[0].clone();
+        board.deal(deck, rng);
+        let mut hands = vec![(board.white_cards.clone(), board.black_cards.clone())];
+        for next in &self.boards[1..] {
+            board.move_piece(next.last_move.as_ref().unwrap());
+            if let Some(card) = next.last_card {
+                let Some(index) = board.hand(board.active).iter().rposition(|c| *c == card) else {
+                    return false;
+                };
+                board.play_card(index, deck, rng);
+            }
+            if board.last_event != next.last_event {
+                return false;
+            }
+            hands.push((board.white_cards.clone(), board.black_cards.clone()));
+        }
+        for (board, (white, black)) in self.boards.iter_mut().zip(hands) {
+            board.white_cards = white;
+            board.black_cards = black;
+        }
+        true
+    }
+}
@@ -1,8 +1,28 @@
-use crate::*;
+use crate::{ai::*, chess::*, clock::*, deck::*, lobby::*, pgn, record::*};
 use actix::*;
-use chess::*;
+use rand::{random, seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+const EMPTY_ROOM_TIMEOUT: Duration = Duration::from_secs(30);
+const CLOCK_INTERVAL: Duration = Duration::from_millis(100);
+const CARD_TIMEOUT: Duration = Duration::from_secs(15);
+const AI_COLOR: Color = Color::Black;
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub time_control: Option<TimeControl>,
+    pub deck: DeckConfig,
+    pub choose_cards: bool,
+    pub choose_events: bool,
+    pub seed: Option<u64>,
+    pub ai: Option<Difficulty>,
+    pub record: Option<GameRecord>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 enum Role {
@@ -10,6 +30,13 @@ enum Role {
     Spectator,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Move,
+    Card,
+    Event(Card),
+}
+
 #[derive(Message, Serialize, Deserialize)]
 #[rtype(result = "()")]
 pub struct Message {
@@ -18,18 +45,29 @@ pub struct Message {
     state: GameState,
     last: Option<Move>,
     last_event: Option<Event>,
-    last_card: Option<usize>,
+    event_description: Option<String>,
+    last_card: Option<Card>,
     check: Option<Location>,
+    frozen: Option<Location>,
     role: Role,
-    half_moves: usize,
-    my_cards: Vec<usize>,
-    opponent_cards: Vec<usize>,
+    plies: usize,
+    my_cards: Vec<Card>,
+    opponent_cards: Vec<Card>,
+    restart_votes: Vec<Color>,
+    draw_offer: Option<Color>,
+    clock: Option<ClockState>,
+    deck: DeckConfig,
+    phase: Phase,
+    active: Color,
+    events: Vec<Event>,
 }
 
 #[derive(Message)]
 #[rtype(usize)]
 pub struct Connect {
     pub addr: Recipient<Message>,
+    pub admin: bool,
+    pub name: Option<String>,
 }
 
 #[derive(Message)]
@@ -38,71 +76,585 @@ pub struct Disconnect {
     pub id: usize,
 }
 
-#[derive(Message, Serialize, Deserialize)]
-#[rtype(result = "()")]
+#[derive(Message)]
+#[rtype(String)]
+pub struct ExportPgn;
+
+#[derive(Serialize, Deserialize)]
 pub enum Request {
-    Move(Move),
+    Move {
+        from: Location,
+        to: Location,
+        promote_to: Option<PieceType>,
+    },
+    Uci(String),
     Restart,
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    PlayCard(usize),
+    PassCard,
+    ChooseEvent(usize),
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<(), RequestError>")]
+pub struct ClientRequest {
+    pub id: usize,
+    pub request: Request,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestError {
+    InvalidRequest,
+    IllegalMove,
+    NotAPlayer,
+    NotYourTurn,
+    GameOver,
+    NoDrawOffer,
+    CardPending,
+    NoCardPending,
+    InvalidCard,
+    NoEventPending,
+    InvalidEvent,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct AiRequest {
+    ticket: usize,
+    request: Option<Request>,
+}
+
+enum Decision {
+    Play(Phase),
+    Draw,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Think {
+    ticket: usize,
+    decision: Decision,
+    board: Board,
+    rules: Rules,
+    difficulty: Difficulty,
+    limit: Duration,
+    server: Addr<Server>,
+}
+
+struct Engine;
+
+impl Actor for Engine {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Think> for Engine {
+    type Result = ();
+
+    fn handle(&mut self, msg: Think, _: &mut SyncContext<Self>) {
+        let (board, rules) = (&msg.board, &msg.rules);
+        let (difficulty, limit) = (msg.difficulty, msg.limit);
+        let request = match msg.decision {
+            Decision::Draw => Some(if accepts_draw(board, rules, AI_COLOR, difficulty, limit) {
+                Request::AcceptDraw
+            } else {
+                Request::DeclineDraw
+            }),
+            Decision::Play(Phase::Move) => {
+                best_move(board, rules, difficulty, limit).map(|mv| Request::Uci(mv.to_string()))
+            }
+            Decision::Play(Phase::Card) => {
+                Some(match choose_card(board, rules, difficulty, limit) {
+                    Some(index) => Request::PlayCard(index),
+                    None => Request::PassCard,
+                })
+            }
+            Decision::Play(Phase::Event(card)) => {
+                choose_event(board, rules, card, difficulty, limit).map(Request::ChooseEvent)
+            }
+        };
+        msg.server.do_send(AiRequest {
+            ticket: msg.ticket,
+            request,
+        });
+    }
+}
+
+#[derive(Serialize)]
+pub struct ErrorMessage {
+    pub error: RequestError,
 }
 
-#[derive(Debug, Default)]
 pub struct Server {
+    room: String,
+    lobby: Addr<Lobby>,
+    config: Config,
     sessions: HashMap<usize, Recipient<Message>>,
     white: Option<usize>,
     black: Option<usize>,
-    board: Board,
+    white_name: Option<String>,
+    black_name: Option<String>,
+    admins: HashSet<usize>,
+    restart_votes: Vec<Color>,
+    draw_offer: Option<Color>,
+    record: GameRecord,
+    clock: Option<Clock>,
+    phase: Phase,
+    deadline: Option<Instant>,
+    rng: ChaCha8Rng,
+    thinking: bool,
+    engine: Option<Addr<Engine>>,
+    ticket: usize,
     id: usize,
 }
 
 impl Server {
-    fn send_state(&self) {
-        let pieces = self.board.pieces;
-        let moves = self.board.all_possible_moves();
-        let state = self.board.game_state(&moves);
-        let last = self.board.last_move.clone();
-        let last_event = self.board.last_event;
-        let last_card = self.board.last_card;
-        let check = self.board.get_check();
-        let half_moves = self.board.half_moves;
-        for (id, addr) in &self.sessions {
-            let role = if Some(*id) == self.white {
-                Role::Player(Color::White)
-            } else if Some(*id) == self.black {
-                Role::Player(Color::Black)
+    pub fn new(room: String, lobby: Addr<Lobby>, mut config: Config) -> Self {
+        let clock = config.time_control.map(Clock::new);
+        let (record, rng) = match config.record.clone() {
+            Some(record) => {
+                if let Some(deck) = record.deck() {
+                    config.deck = deck.clone();
+                }
+                Self::resume(record, &config)
+            }
+            None => Self::new_record(&config, config.seed.unwrap_or_else(random)),
+        };
+        let black_name = config.ai.map(|d| format!("AI ({:?})", d));
+        Self {
+            room,
+            lobby,
+            config,
+            sessions: HashMap::new(),
+            white: None,
+            black: None,
+            white_name: None,
+            black_name,
+            admins: HashSet::new(),
+            restart_votes: vec![],
+            draw_offer: None,
+            record,
+            clock,
+            phase: Phase::Move,
+            deadline: None,
+            rng,
+            thinking: false,
+            engine: None,
+            ticket: 0,
+            id: 0,
+        }
+    }
+
+    fn role(&self, id: usize) -> Role {
+        if Some(id) == self.white {
+            Role::Player(Color::White)
+        } else if Some(id) == self.black {
+            Role::Player(Color::Black)
+        } else {
+            Role::Spectator
+        }
+    }
+
+    fn seat(&self, color: Color) -> Option<usize> {
+        if color.is_white() {
+            self.white
+        } else {
+            self.black
+        }
+    }
+
+    fn new_record(config: &Config, seed: u64) -> (GameRecord, ChaCha8Rng) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut board = Board::default();
+        board.deal(&config.deck, &mut rng);
+        let mut record = GameRecord::new(board);
+        record.set_deck(config.deck.clone());
+        if Self::reproducible(config) {
+            record.set_seed(seed);
+        }
+        (record, rng)
+    }
+
+    fn resume(mut record: GameRecord, config: &Config) -> (GameRecord, ChaCha8Rng) {
+        record.set_deck(config.deck.clone());
+        if let Some(seed) = record.seed() {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            if record.redeal(&config.deck, &mut rng) {
+                if !Self::reproducible(config) {
+                    record.clear_seed();
+                }
+                return (record, rng);
+            }
+        }
+        // The recorded cards did not come from the seed, so only the hands from here on are dealt.
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed.unwrap_or_else(random));
+        record.clear_seed();
+        record.board_mut().deal(&config.deck, &mut rng);
+        (record, rng)
+    }
+
+    // The record keeps neither which copy of a card was chosen nor whether an event was chosen
+    // or picked at random on a timeout, so only automatic play can be replayed from the seed.
+    fn reproducible(config: &Config) -> bool {
+        !config.choose_cards && !config.choose_events
+    }
+
+    fn restart(&mut self) {
+        let seed = self.config.seed.unwrap_or_else(random);
+        (self.record, self.rng) = Self::new_record(&self.config, seed);
+        self.clock = self.config.time_control.map(Clock::new);
+        self.restart_votes.clear();
+        self.draw_offer = None;
+        self.phase = Phase::Move;
+        self.deadline = None;
+        self.ticket += 1;
+    }
+
+    fn conclude(&mut self, outcome: GameState) {
+        self.record.board_mut().conclude(outcome);
+        self.stop_clock();
+        self.draw_offer = None;
+        self.phase = Phase::Move;
+        self.deadline = None;
+        self.ticket += 1;
+    }
+
+    fn stop_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(Instant::now());
+        }
+    }
+
+    fn check_clock(&mut self) {
+        let Some(clock) = &self.clock else {
+            return;
+        };
+        if let Some(color) = clock.flagged(Instant::now()) {
+            self.conclude(GameState::Timeout(color));
+            self.send_state();
+        }
+    }
+
+    fn check_deadline(&mut self) {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {}
+            _ => return,
+        }
+        let board = self.record.board_mut();
+        match self.phase {
+            Phase::Move => {}
+            Phase::Card => board.draw_card(&self.config.deck, &mut self.rng),
+            Phase::Event(card) => {
+                let ev = board.event_candidates(card).choose(&mut self.rng).copied();
+                board.replay_card(card, ev);
+            }
+        }
+        self.phase = Phase::Move;
+        self.deadline = None;
+        self.ticket += 1;
+        self.send_state();
+    }
+
+    fn check_ai(&mut self, ctx: &mut Context<Self>) {
+        let Some(difficulty) = self.config.ai else {
+            return;
+        };
+        let board = self.record.board();
+        if self.thinking || board.is_game_over() {
+            return;
+        }
+        let decision = if self.draw_offer == Some(AI_COLOR.other()) {
+            Decision::Draw
+        } else if board.active == AI_COLOR {
+            Decision::Play(self.phase)
+        } else {
+            return;
+        };
+        let board = board.clone();
+        let rules = Rules {
+            deck: self.config.deck.clone(),
+            choose_cards: self.config.choose_cards,
+            choose_events: self.config.choose_events,
+        };
+        let mut limit = difficulty.time();
+        if let Some(clock) = &self.clock {
+            limit = limit.min(clock.remaining(AI_COLOR, Instant::now()) / 20);
+        }
+        self.thinking = true;
+        let engine = self
+            .engine
+            .get_or_insert_with(|| SyncArbiter::start(1, || Engine));
+        engine.do_send(Think {
+            ticket: self.ticket,
+            decision,
+            board,
+            rules,
+            difficulty,
+            limit,
+            server: ctx.address(),
+        });
+    }
+
+    // Keeps the game going when the engine has no usable answer.
+    fn fall_back(&mut self, role: Role) {
+        let result = match self.phase {
+            Phase::Move => self.play(role, |board| board.legal_moves().into_iter().next()),
+            Phase::Card => self.play_card(role, None),
+            Phase::Event(_) => self.choose_event(role, 0),
+        };
+        if let Err(err) = result {
+            println!("AI fallback failed in room {}: {:?}", self.room, err);
+        }
+    }
+
+    fn use_card(&mut self, index: usize) -> bool {
+        let deck = &self.config.deck;
+        let board = self.record.board_mut();
+        if !self.config.choose_events {
+            if !board.play_card(index, deck, &mut self.rng) {
+                return false;
+            }
+            self.phase = Phase::Move;
+            self.deadline = None;
+            return true;
+        }
+        let Some(card) = board.take_card(index, deck, &mut self.rng) else {
+            return false;
+        };
+        if board.event_candidates(card).is_empty() {
+            self.phase = Phase::Move;
+            self.deadline = None;
+        } else {
+            self.phase = Phase::Event(card);
+            self.deadline = Some(Instant::now() + CARD_TIMEOUT);
+        }
+        true
+    }
+
+    fn acting_player(&self, role: Role) -> Result<Color, RequestError> {
+        let color = self.ongoing_player(role)?;
+        if color != self.record.board().active {
+            return Err(RequestError::NotYourTurn);
+        }
+        Ok(color)
+    }
+
+    fn ongoing_player(&self, role: Role) -> Result<Color, RequestError> {
+        let Role::Player(color) = role else {
+            return Err(RequestError::NotAPlayer);
+        };
+        if self.record.board().is_game_over() {
+            return Err(RequestError::GameOver);
+        }
+        Ok(color)
+    }
+
+    fn report(&self) {
+        let info = RoomInfo {
+            id: self.room.clone(),
+            white: self.white.is_some(),
+            black: self.black.is_some() || self.config.ai.is_some(),
+            spectators: self.sessions.len()
+                - self.white.is_some() as usize
+                - self.black.is_some() as usize,
+            deck: self.config.deck.name.clone(),
+        };
+        self.lobby.do_send(UpdateRoom { info });
+    }
+
+    fn schedule_cleanup(&self, ctx: &mut Context<Self>) {
+        ctx.run_later(EMPTY_ROOM_TIMEOUT, |act, ctx| {
+            if act.sessions.is_empty() {
+                act.lobby.do_send(RemoveRoom {
+                    id: act.room.clone(),
+                });
+                ctx.stop();
+            }
+        });
+    }
+
+    fn play<F: FnOnce(&Board) -> Option<Move>>(
+        &mut self,
+        role: Role,
+        resolve: F,
+    ) -> Result<(), RequestError> {
+        let color = self.ongoing_player(role)?;
+        let mut board = self.record.board().clone();
+        if color != board.active {
+            return Err(RequestError::NotYourTurn);
+        }
+        if self.phase != Phase::Move {
+            return Err(RequestError::CardPending);
+        }
+        let mv = resolve(&board).ok_or(RequestError::IllegalMove)?;
+        if let Some(clock) = &mut self.clock {
+            clock.punch(color, Instant::now());
+        }
+        board.move_piece(&mv);
+        self.record.push(board);
+        if !self.record.board().is_game_over() {
+            if self.config.choose_cards {
+                self.phase = Phase::Card;
+                self.deadline = Some(Instant::now() + CARD_TIMEOUT);
             } else {
-                Role::Spectator
-            };
-            let moves = match role {
-                Role::Player(color) if color == self.board.active => moves.clone(),
-                _ => vec![],
-            };
-            let my_cards = match role {
-                Role::Player(color) if !color.is_white() => self.board.black_cards.clone(),
-                _ => self.board.white_cards.clone(),
-            };
-            let opponent_cards = match role {
-                Role::Player(color) if !color.is_white() => self.board.white_cards.clone(),
-                _ => self.board.black_cards.clone(),
-            };
+                let index = self.record.board().hand(color.other()).len() - 1;
+                self.use_card(index);
+            }
+        }
+        if self.record.board().is_game_over() {
+            self.stop_clock();
+        }
+        self.restart_votes.clear();
+        if self.draw_offer == Some(color.other()) {
+            self.draw_offer = None;
+        }
+        self.send_state();
+        Ok(())
+    }
+
+    fn play_card(&mut self, role: Role, index: Option<usize>) -> Result<(), RequestError> {
+        self.acting_player(role)?;
+        if self.phase != Phase::Card {
+            return Err(RequestError::NoCardPending);
+        }
+        match index {
+            Some(index) => {
+                if !self.use_card(index) {
+                    return Err(RequestError::InvalidCard);
+                }
+            }
+            None => {
+                self.phase = Phase::Move;
+                self.deadline = None;
+            }
+        }
+        self.send_state();
+        Ok(())
+    }
+
+    fn choose_event(&mut self, role: Role, index: usize) -> Result<(), RequestError> {
+        self.acting_player(role)?;
+        let Phase::Event(card) = self.phase else {
+            return Err(RequestError::NoEventPending);
+        };
+        let board = self.record.board_mut();
+        let ev = board.event_candidates(card).get(index).copied();
+        if ev.is_none() || !board.replay_card(card, ev) {
+            return Err(RequestError::InvalidEvent);
+        }
+        self.phase = Phase::Move;
+        self.deadline = None;
+        self.send_state();
+        Ok(())
+    }
+
+    fn answer_draw(&mut self, role: Role, accept: bool) -> Result<(), RequestError> {
+        let color = self.ongoing_player(role)?;
+        if self.draw_offer != Some(color.other()) {
+            return Err(RequestError::NoDrawOffer);
+        }
+        if accept {
+            self.conclude(GameState::DrawAgreed);
+        } else {
+            self.draw_offer = None;
+        }
+        self.send_state();
+        Ok(())
+    }
+
+    fn message(
+        &self,
+        board: &Board,
+        state: GameState,
+        moves: &[(Location, Vec<Move>)],
+        role: Role,
+        phase: Phase,
+    ) -> Message {
+        let moves = match role {
+            Role::Player(color)
+                if color == board.active && state == GameState::Normal && phase == Phase::Move =>
+            {
+                moves.to_vec()
+            }
+            _ => vec![],
+        };
+        let events = match (role, phase) {
+            (Role::Player(color), Phase::Event(card)) if color == board.active => {
+                board.event_candidates(card)
+            }
+            _ => vec![],
+        };
+        let my_cards = match role {
+            Role::Player(color) if !color.is_white() => board.black_cards.clone(),
+            _ => board.white_cards.clone(),
+        };
+        let opponent_cards = match role {
+            Role::Player(color) if !color.is_white() => board.white_cards.clone(),
+            _ => board.black_cards.clone(),
+        };
+        Message {
+            pieces: board.pieces(),
+            moves,
+            state,
+            last: board.last_move.clone(),
+            last_event: board.last_event,
+            event_description: board.last_event.map(|ev| ev.describe()),
+            last_card: board.last_card,
+            check: board.get_check(),
+            frozen: board.frozen(),
+            role,
+            plies: board.plies,
+            my_cards,
+            opponent_cards,
+            restart_votes: self.restart_votes.clone(),
+            draw_offer: self.draw_offer,
+            clock: self.clock.as_ref().map(|c| c.state(Instant::now())),
+            deck: self.config.deck.clone(),
+            phase,
+            active: board.active,
+            events,
+        }
+    }
+
+    fn send_history(&self, id: usize) {
+        let Some(addr) = self.sessions.get(&id) else {
+            return;
+        };
+        let role = self.role(id);
+        for board in self.record.history() {
             addr.do_send(Message {
-                pieces,
-                moves,
-                state,
-                last: last.clone(),
-                last_event,
-                last_card,
-                check,
-                role,
-                half_moves,
-                my_cards,
-                opponent_cards,
+                restart_votes: vec![],
+                draw_offer: None,
+                clock: None,
+                ..self.message(board, GameState::Normal, &[], role, Phase::Move)
             });
         }
     }
+
+    fn send_state(&self) {
+        let board = self.record.board();
+        let moves = board.all_possible_moves();
+        let state = board.game_state(&moves);
+        for (id, addr) in &self.sessions {
+            let role = self.role(*id);
+            addr.do_send(self.message(board, state, &moves, role, self.phase));
+        }
+    }
 }
 
 impl Actor for Server {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.schedule_cleanup(ctx);
+        ctx.run_interval(CLOCK_INTERVAL, |act, ctx| {
+            act.check_clock();
+            act.check_deadline();
+            act.check_ai(ctx);
+        });
+    }
 }
 
 impl Handler<Connect> for Server {
@@ -110,14 +662,21 @@ impl Handler<Connect> for Server {
 
     fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
         let id = self.id;
-        println!("connected {}", id);
+        println!("connected {} to room {}", id, self.room);
         self.id += 1;
         self.sessions.insert(id, msg.addr);
+        if msg.admin {
+            self.admins.insert(id);
+        }
         if self.white.is_none() {
             self.white = Some(id);
-        } else if self.black.is_none() {
+            self.white_name = msg.name;
+        } else if self.black.is_none() && self.config.ai.is_none() {
             self.black = Some(id);
+            self.black_name = msg.name;
         }
+        self.report();
+        self.send_history(id);
         self.send_state();
         id
     }
@@ -126,33 +685,122 @@ impl Handler<Connect> for Server {
 impl Handler<Disconnect> for Server {
     type Result = ();
 
-    fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
-        println!("disconnected {}", msg.id);
+    fn handle(&mut self, msg: Disconnect, ctx: &mut Context<Self>) {
+        println!("disconnected {} from room {}", msg.id, self.room);
         self.sessions.remove(&msg.id);
+        self.admins.remove(&msg.id);
+        if let Role::Player(color) = self.role(msg.id) {
+            self.restart_votes.retain(|c| *c != color);
+        }
         if self.white == Some(msg.id) {
             self.white = None;
         } else if self.black == Some(msg.id) {
             self.black = None;
         }
+        self.report();
+        if self.sessions.is_empty() {
+            self.schedule_cleanup(ctx);
+        }
     }
 }
 
-impl Handler<Request> for Server {
-    type Result = ();
+impl Handler<ClientRequest> for Server {
+    type Result = Result<(), RequestError>;
 
-    fn handle(&mut self, msg: Request, _: &mut Context<Self>) {
-        match msg {
-            Request::Move(mv) => {
-                self.board.move_piece(&mv);
-                if !self.board.is_game_over() {
-                    self.board.draw_card();
+    fn handle(&mut self, msg: ClientRequest, _: &mut Context<Self>) -> Self::Result {
+        let role = self.role(msg.id);
+        match msg.request {
+            Request::Move {
+                from,
+                to,
+                promote_to,
+            } => self.play(role, |board| board.find_move(from, to, promote_to))?,
+            Request::Uci(uci) => self.play(role, |board| board.parse_uci(&uci))?,
+            Request::Restart => {
+                if self.admins.contains(&msg.id) {
+                    self.restart();
+                } else {
+                    let Role::Player(color) = role else {
+                        return Err(RequestError::NotAPlayer);
+                    };
+                    if !self.restart_votes.contains(&color) {
+                        self.restart_votes.push(color);
+                    }
+                    let agreed = [Color::White, Color::Black]
+                        .into_iter()
+                        .all(|c| self.seat(c).is_none() || self.restart_votes.contains(&c));
+                    if agreed {
+                        self.restart();
+                    }
                 }
                 self.send_state();
             }
-            Request::Restart => {
-                self.board = Board::default();
+            Request::Resign => {
+                let color = self.ongoing_player(role)?;
+                self.conclude(GameState::Resigned(color));
                 self.send_state();
             }
+            Request::OfferDraw => {
+                let color = self.ongoing_player(role)?;
+                if self.draw_offer == Some(color.other()) {
+                    self.conclude(GameState::DrawAgreed);
+                } else {
+                    self.draw_offer = Some(color);
+                }
+                self.send_state();
+            }
+            Request::AcceptDraw => self.answer_draw(role, true)?,
+            Request::DeclineDraw => self.answer_draw(role, false)?,
+            Request::PlayCard(index) => self.play_card(role, Some(index))?,
+            Request::PassCard => self.play_card(role, None)?,
+            Request::ChooseEvent(index) => self.choose_event(role, index)?,
+        }
+        Ok(())
+    }
+}
+
+impl Handler<AiRequest> for Server {
+    type Result = ();
+
+    fn handle(&mut self, msg: AiRequest, _: &mut Context<Self>) {
+        self.thinking = false;
+        if msg.ticket != self.ticket {
+            return;
+        }
+        let role = Role::Player(AI_COLOR);
+        let draw = matches!(
+            msg.request,
+            Some(Request::AcceptDraw | Request::DeclineDraw)
+        );
+        let result = match msg.request {
+            Some(Request::AcceptDraw) => self.answer_draw(role, true),
+            Some(Request::DeclineDraw) => self.answer_draw(role, false),
+            Some(Request::Uci(uci)) => self.play(role, |board| board.parse_uci(&uci)),
+            Some(Request::PlayCard(index)) => self.play_card(role, Some(index)),
+            Some(Request::PassCard) => self.play_card(role, None),
+            Some(Request::ChooseEvent(index)) => self.choose_event(role, index),
+            _ => Err(RequestError::InvalidRequest),
+        };
+        if let Err(err) = result {
+            println!("AI request failed in room {}: {:?}", self.room, err);
+            if !draw {
+                self.fall_back(role);
+            }
         }
     }
 }
+
+impl Handler<ExportPgn> for Server {
+    type Result = String;
+
+    fn handle(&mut self, _: ExportPgn, _: &mut Context<Self>) -> Self::Result {
+        let unknown = || "?".to_string();
+        let headers = pgn::Headers {
+            event: format!("Room {}", self.room),
+            white: self.white_name.clone().unwrap_or_else(unknown),
+            black: self.black_name.clone().unwrap_or_else(unknown),
+            ..pgn::Headers::default()
+        };
+        pgn::export(&self.record, &headers)
+    }
+}
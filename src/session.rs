@@ -10,6 +10,8 @@ const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 pub struct WsSession {
     pub id: usize,
     pub hb: Instant,
+    pub admin: bool,
+    pub name: Option<String>,
     pub addr: Addr<server::Server>,
 }
 
@@ -24,6 +26,11 @@ impl WsSession {
             ctx.ping(b"");
         });
     }
+
+    fn send_error(ctx: &mut ws::WebsocketContext<Self>, error: server::RequestError) {
+        let msg = server::ErrorMessage { error };
+        ctx.text(serde_json::to_string(&msg).unwrap());
+    }
 }
 
 impl Actor for WsSession {
@@ -36,6 +43,8 @@ impl Actor for WsSession {
         self.addr
             .send(server::Connect {
                 addr: addr.recipient(),
+                admin: self.admin,
+                name: self.name.clone(),
             })
             .into_actor(self)
             .then(|res, act, ctx| {
@@ -80,8 +89,24 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                 self.hb = Instant::now();
             }
             ws::Message::Text(text) => {
-                let req: server::Request = serde_json::from_str(&text).unwrap();
-                self.addr.do_send(req);
+                let Ok(request) = serde_json::from_str(&text) else {
+                    Self::send_error(ctx, server::RequestError::InvalidRequest);
+                    return;
+                };
+                let req = server::ClientRequest {
+                    id: self.id,
+                    request,
+                };
+                self.addr
+                    .send(req)
+                    .into_actor(self)
+                    .then(|res, _, ctx| {
+                        if let Ok(Err(error)) = res {
+                            Self::send_error(ctx, error);
+                        }
+                        fut::ready(())
+                    })
+                    .spawn(ctx);
             }
             ws::Message::Binary(_) => {}
             ws::Message::Close(reason) => {
@@ -1,9 +1,22 @@
 document.addEventListener("DOMContentLoaded", () => {
+  const params = new URLSearchParams(window.location.search);
+  const room = params.get("room");
+  if (!room) {
+    showLobby();
+    return;
+  }
+
   const chessboard = document.getElementById("chessboard");
   const promotions = document.getElementById("promotions");
   const zoomInButton = document.getElementById("zoomIn");
   const zoomOutButton = document.getElementById("zoomOut");
   const restartButton = document.getElementById("restart");
+  const resignButton = document.getElementById("resign");
+  const pgnButton = document.getElementById("pgn");
+  const offerDrawButton = document.getElementById("offerDraw");
+  const acceptDrawButton = document.getElementById("acceptDraw");
+  const declineDrawButton = document.getElementById("declineDraw");
+  const drawOffer = document.getElementById("drawOffer");
   const prevButton = document.getElementById("prev");
   const nextButton = document.getElementById("next");
   const queenButton = document.getElementById("queen");
@@ -13,6 +26,9 @@ document.addEventListener("DOMContentLoaded", () => {
   const resultText = document.getElementById("result");
   const titleText = document.getElementById("title");
   const descText = document.getElementById("description");
+  const noticeText = document.getElementById("notice");
+  const whiteClock = document.getElementById("whiteClock");
+  const blackClock = document.getElementById("blackClock");
   const myText = document.getElementById("myCardInfo");
   const opText = document.getElementById("opponentCardInfo");
   promotions.style.display = "none";
@@ -27,6 +43,8 @@ document.addEventListener("DOMContentLoaded", () => {
   let targets = [];
   let waiting = true;
   let promotionMove = null;
+  let clock = null;
+  let clockReceived = 0;
   
   zoomInButton.addEventListener("click", () => {
     size += 10;
@@ -69,12 +87,24 @@ document.addEventListener("DOMContentLoaded", () => {
     }
   });
 
-  restartButton.addEventListener("click", () => {
+  function sendRequest(request) {
     if (socket) {
-      socket.send(JSON.stringify("Restart"));
+      socket.send(JSON.stringify(request));
       waiting = true;
     }
+  }
+
+  restartButton.addEventListener("click", () => sendRequest("Restart"));
+  resignButton.addEventListener("click", () => {
+    if (confirm("기권하시겠습니까?"))
+      sendRequest("Resign");
   });
+  pgnButton.addEventListener("click", () => {
+    window.open(`/rooms/${encodeURIComponent(room)}/pgn`);
+  });
+  offerDrawButton.addEventListener("click", () => sendRequest("OfferDraw"));
+  acceptDrawButton.addEventListener("click", () => sendRequest("AcceptDraw"));
+  declineDrawButton.addEventListener("click", () => sendRequest("DeclineDraw"));
 
   function promotionClick(event) {
     promotions.style.display = "none";
@@ -83,10 +113,15 @@ document.addEventListener("DOMContentLoaded", () => {
       selected = null;
       targets = [];
       move = true;
-      if (socket) {
-        socket.send(JSON.stringify({ "Move": promotionMove }));
-        waiting = true;
-      }
+      sendMove(promotionMove);
+    }
+  }
+
+  function sendMove(mv) {
+    if (socket) {
+      const { from, to, promote_to } = mv;
+      socket.send(JSON.stringify({ "Move": { from, to, promote_to } }));
+      waiting = true;
     }
   }
 
@@ -95,6 +130,28 @@ document.addEventListener("DOMContentLoaded", () => {
   bishopButton.addEventListener("click", promotionClick);
   knightButton.addEventListener("click", promotionClick);
 
+  function formatTime(ms) {
+    const total = Math.ceil(Math.max(ms, 0) / 1000);
+    const minutes = Math.floor(total / 60);
+    const seconds = total % 60;
+    return `${minutes}:${seconds.toString().padStart(2, "0")}`;
+  }
+
+  function drawClocks() {
+    if (!clock) {
+      whiteClock.innerText = "";
+      blackClock.innerText = "";
+      return;
+    }
+    const elapsed = performance.now() - clockReceived;
+    const white = clock.white - (clock.running === "White" ? elapsed : 0);
+    const black = clock.black - (clock.running === "Black" ? elapsed : 0);
+    whiteClock.innerText = `백 ${formatTime(white)}`;
+    blackClock.innerText = `흑 ${formatTime(black)}`;
+  }
+
+  setInterval(drawClocks, 200);
+
   function pieceToFile(piece) {
     let s = piece.color === "White" ? "w" : "b";
     switch (piece.typ) {
@@ -153,28 +210,37 @@ document.addEventListener("DOMContentLoaded", () => {
     chessboard.appendChild(overlay);
   }
 
-  const cardNames = [
-    "효과 없음",
-    "말에 오르다",
-    "말에서 내리다",
-    "융합",
-    "분열",
-    "합체",
-    "산산조각",
-    "교대 근무",
-    "지구는 둥그니까",
-    "전력 질주",
-    "이사",
-  ];
+  let cardInfo = [];
+
+  fetch("/cards")
+    .then(res => res.json())
+    .then(cards => {
+      cardInfo = cards;
+      if (messages.length > 0) draw();
+    });
+
+  function cardName(card) {
+    const info = cardInfo.find(info => info.card === card);
+    return info ? info.name : card;
+  }
+
+  function eventLabel(event) {
+    const [name, value] = event.Custom
+      ? [event.Custom.card, event.Custom.locations]
+      : Object.entries(event)[0];
+    const parts = [value].flat().map(v =>
+      typeof v === "number" ? `${v + 1}` : `${"abcdefgh"[v.file]}${v.rank + 1}`
+    );
+    return `${cardName(name)}: ${parts.join(" ")}`;
+  }
 
   function summarizeCards(cards, div) {
-    const arr = Array(11).fill(0);
-    for (card of cards)
-      arr[card] += 1;
-    for (let i = 0; i < 11; i++) {
-      if (arr[i] > 0) {
+    for (const info of cardInfo) {
+      const count = cards.filter(card => card === info.card).length;
+      if (count > 0) {
         const p = document.createElement("p");
-        p.innerText = `${cardNames[i]}: ${arr[i]}장`;
+        p.innerText = `${info.name}: ${count}장`;
+        p.title = info.description;
         div.appendChild(p);
       }
     }
@@ -188,6 +254,8 @@ document.addEventListener("DOMContentLoaded", () => {
     resultText.innerText = "";
     titleText.innerText = "";
     descText.innerText = "";
+    noticeText.innerText = "";
+    drawOffer.style.display = "none";
     myText.innerHTML = "";
     opText.innerHTML = "";
 
@@ -202,6 +270,20 @@ document.addEventListener("DOMContentLoaded", () => {
 
       if (message.state === "Stalemate") {
         resultText.innerText = "스테일메이트";
+      } else if (message.state === "FiftyMoves") {
+        resultText.innerText = "무승부 (50수 규칙)";
+      } else if (message.state === "Repetition") {
+        resultText.innerText = "무승부 (3회 반복)";
+      } else if (message.state === "InsufficientMaterial") {
+        resultText.innerText = "무승부 (기물 부족)";
+      } else if (message.state === "DrawAgreed") {
+        resultText.innerText = "무승부 (합의)";
+      } else if (message.state.Resigned) {
+        const color = message.state.Resigned === "White" ? "흑" : "백";
+        resultText.innerText = `${color} 승리 (기권)`;
+      } else if (message.state.Timeout) {
+        const color = message.state.Timeout === "White" ? "흑" : "백";
+        resultText.innerText = `${color} 승리 (시간 초과)`;
       } else if (message.state !== "Normal") {
         const color = message.state.Checkmate === "White" ? "백" : "흑";
         resultText.innerText = `${color} 승리`;
@@ -218,48 +300,95 @@ document.addEventListener("DOMContentLoaded", () => {
         addOverlay(255, 0, 0, 0.2, message.check.file, message.check.rank);
       }
 
+      if (message.frozen) {
+        addOverlay(0, 255, 255, 0.3, message.frozen.file, message.frozen.rank);
+      }
+
+      if (message.restart_votes.length > 0) {
+        const colors = message.restart_votes.map(c => c === "White" ? "백" : "흑");
+        noticeText.innerText = `재시작 요청: ${colors.join(", ")}`;
+      }
+
+      if (message.draw_offer) {
+        const color = message.draw_offer === "White" ? "백" : "흑";
+        noticeText.innerText = `${color}이 무승부를 제안했습니다.`;
+        if (message.role.Player && message.role.Player !== message.draw_offer)
+          drawOffer.style.display = "block";
+      }
+
+      if (message.deck) {
+        const p = document.createElement("p");
+        p.innerText = `덱: ${message.deck.name} (손패 ${message.deck.hand_size}장)`;
+        myText.appendChild(p);
+      }
+
       if (message.my_cards)
         summarizeCards(message.my_cards, myText);
 
       if (message.opponent_cards)
         summarizeCards(message.opponent_cards, opText);
 
+      const latest = cursor === messages.length - 1;
+      if (latest && message.phase === "Card" && message.role.Player === message.active) {
+        noticeText.innerText = "사용할 카드를 고르세요.";
+        message.my_cards.forEach((card, i) => {
+          const button = document.createElement("button");
+          button.innerText = cardName(card);
+          button.addEventListener("click", () => sendRequest({ "PlayCard": i }));
+          myText.appendChild(button);
+        });
+        const pass = document.createElement("button");
+        pass.innerText = "패스";
+        pass.addEventListener("click", () => sendRequest("PassCard"));
+        myText.appendChild(pass);
+      }
+
+      if (latest && message.events.length > 0) {
+        noticeText.innerText = "카드의 효과를 고르세요.";
+        message.events.forEach((event, i) => {
+          const button = document.createElement("button");
+          button.innerText = eventLabel(event);
+          button.addEventListener("click", () => sendRequest({ "ChooseEvent": i }));
+          myText.appendChild(button);
+        });
+      }
+
       if (message.state === "Normal") {
         if (message.last_event) {
           if (message.last_event.Swap) {
             const ev = message.last_event.Swap;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[7];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "두 기물의 위치가 바뀌었습니다.";
           } else if (message.last_event.KnightToBishop) {
             const ev = message.last_event.KnightToBishop;
             addOverlay(0, 0, 255, 0.2, ev.file, ev.rank);
-            titleText.innerText = cardNames[2];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "나이트가 비숍이 되었습니다.";
           } else if (message.last_event.BishopToKnight) {
             const ev = message.last_event.BishopToKnight;
             addOverlay(0, 0, 255, 0.2, ev.file, ev.rank);
-            titleText.innerText = cardNames[1];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "비숍이 나이트가 되었습니다.";
           } else if (message.last_event.RooksToQueen) {
             const ev = message.last_event.RooksToQueen;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[3];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "룩 두 개가 퀸이 되었습니다.";
           } else if (message.last_event.QueenToRooks) {
             const ev = message.last_event.QueenToRooks;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[4];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "퀸이 룩 두 개가 되었습니다.";
           } else if (message.last_event.PawnRun) {
             const ev = message.last_event.PawnRun;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[9];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "폰이 앞으로 달려갔습니다.";
           } else if (message.last_event.PawnsToQueen) {
             const ev = message.last_event.PawnsToQueen;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[5];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "폰 여덟 개가 퀸이 되었습니다.";
           } else if (message.last_event.QueenToPawns) {
             const ev = message.last_event.QueenToPawns;
@@ -270,26 +399,46 @@ document.addEventListener("DOMContentLoaded", () => {
               if (!samePos(ev[0], pos))
                 addOverlay(0, 0, 255, 0.2, file, rank);
             }
-            titleText.innerText = cardNames[6];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "퀸이 폰 여덟 개가 되었습니다.";
           } else if (message.last_event.Rotate) {
             const ev = message.last_event.Rotate;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[8];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "기물이 체스판 반대편으로 건너갔습니다.";
           } else if (message.last_event.KingMove) {
             const ev = message.last_event.KingMove;
             for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
-            titleText.innerText = cardNames[10];
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "킹이 반대편으로 걸어갔습니다.";
+          } else if (message.last_event.DemoteQueen) {
+            const ev = message.last_event.DemoteQueen;
+            addOverlay(0, 0, 255, 0.2, ev.file, ev.rank);
+            titleText.innerText = cardName(message.last_card);
+            descText.innerText = "상대 퀸이 룩이 되었습니다.";
+          } else if (message.last_event.Freeze) {
+            const ev = message.last_event.Freeze;
+            addOverlay(0, 0, 255, 0.2, ev.file, ev.rank);
+            titleText.innerText = cardName(message.last_card);
+            descText.innerText = "상대 기물이 한 턴 동안 얼어붙었습니다.";
+          } else if (message.last_event.PushBack) {
+            const ev = message.last_event.PushBack;
+            for (const e of ev) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
+            titleText.innerText = cardName(message.last_card);
+            descText.innerText = "상대 폰이 한 칸 뒤로 밀려났습니다.";
+          } else if (message.last_event.Custom) {
+            const ev = message.last_event.Custom;
+            for (const e of ev.locations) addOverlay(0, 0, 255, 0.2, e.file, e.rank);
+            titleText.innerText = cardName(message.last_card);
+            descText.innerText = message.event_description;
           }
         } else if (message.last_card !== null) {
-          if (message.last_card === 0) {
-            titleText.innerText = cardNames[0];
+          if (message.last_card === "Blank") {
+            titleText.innerText = cardName(message.last_card);
             descText.innerText = "-";
           } else {
             titleText.innerText = "발동 실패";
-            descText.innerText = cardNames[message.last_card];
+            descText.innerText = cardName(message.last_card);
           }
         }
       }
@@ -347,10 +496,7 @@ document.addEventListener("DOMContentLoaded", () => {
             } else {
               selected = null;
               targets = [];
-              if (socket) {
-                socket.send(JSON.stringify({ "Move": mv }));
-                waiting = true;
-              }
+              sendMove(mv);
             }
           }
         }
@@ -371,7 +517,12 @@ document.addEventListener("DOMContentLoaded", () => {
     const { location } = window;
 
     const proto = location.protocol.startsWith("https") ? "wss" : "ws";
-    const wsUri = `${proto}://${location.host}/ws`;
+    const query = new URLSearchParams();
+    for (const key of ["admin", "name"]) {
+      if (params.get(key))
+        query.set(key, params.get(key));
+    }
+    const wsUri = `${proto}://${location.host}/ws/${encodeURIComponent(room)}?${query}`;
 
     socket = new WebSocket(wsUri);
     socket.onopen = () => {
@@ -379,7 +530,16 @@ document.addEventListener("DOMContentLoaded", () => {
     };
     socket.onmessage = (ev) => {
       const message = JSON.parse(ev.data);
-      if (message.half_moves === 0) messages = [];
+      if (message.error) {
+        console.warn(`request rejected: ${message.error}`);
+        waiting = false;
+        draw();
+        return;
+      }
+      clock = message.clock;
+      clockReceived = performance.now();
+      drawClocks();
+      if (message.plies === 0) messages = [];
       messages.push(message);
       cursor = messages.length - 1;
       waiting = false;
@@ -410,3 +570,67 @@ document.addEventListener("DOMContentLoaded", () => {
 
   connect();
 });
+
+function showLobby() {
+  const roomList = document.getElementById("roomList");
+  const createButton = document.getElementById("createRoom");
+  const timeControl = document.getElementById("timeControl");
+  const deckSelect = document.getElementById("deck");
+  const chooseCards = document.getElementById("chooseCards");
+  const chooseEvents = document.getElementById("chooseEvents");
+  const aiSelect = document.getElementById("ai");
+  const nameInput = document.getElementById("name");
+  const pgnFile = document.getElementById("pgnFile");
+  const importButton = document.getElementById("importPgn");
+  document.body.classList.add("lobby");
+
+  function enter(id) {
+    const query = new URLSearchParams({ room: id });
+    if (nameInput.value)
+      query.set("name", nameInput.value);
+    window.location.search = `?${query}`;
+  }
+
+  createButton.addEventListener("click", async () => {
+    const query = new URLSearchParams(timeControl.value);
+    query.set("deck", deckSelect.value);
+    if (chooseCards.checked)
+      query.set("choose_cards", "true");
+    if (chooseEvents.checked)
+      query.set("choose_events", "true");
+    if (aiSelect.value)
+      query.set("ai", aiSelect.value);
+    const res = await fetch(`/rooms?${query}`, { method: "POST" });
+    const { id } = await res.json();
+    enter(id);
+  });
+
+  importButton.addEventListener("click", async () => {
+    const file = pgnFile.files[0];
+    if (!file)
+      return;
+    const res = await fetch("/rooms/import", { method: "POST", body: await file.text() });
+    if (!res.ok) {
+      alert(await res.text());
+      return;
+    }
+    const { id } = await res.json();
+    enter(id);
+  });
+
+  async function refresh() {
+    const res = await fetch("/rooms");
+    const rooms = await res.json();
+    roomList.innerHTML = "";
+    for (const room of rooms) {
+      const button = document.createElement("button");
+      const players = (room.white ? 1 : 0) + (room.black ? 1 : 0);
+      button.innerText = `${room.id} [${room.deck}] (플레이어 ${players}/2, 관전 ${room.spectators})`;
+      button.addEventListener("click", () => enter(room.id));
+      roomList.appendChild(button);
+    }
+  }
+
+  refresh();
+  setInterval(refresh, 5000);
+}
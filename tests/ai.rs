@@ -0,0 +1,183 @@
+mod common;
+
+use chess::{
+    ai::{self, Difficulty, Rules},
+    chess::{Board, Card, Color},
+    deck::{DeckConfig, Refill},
+    server::{self, ClientRequest, Request},
+};
+use common::Sink;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+const LIMIT: Duration = Duration::from_millis(500);
+
+fn best_move(fen: &str, difficulty: Difficulty) -> String {
+    let board = Board::from_fen(fen).unwrap();
+    ai::best_move(&board, &Rules::default(), difficulty, LIMIT)
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn finds_mate_in_one() {
+    for difficulty in DIFFICULTIES {
+        let mv = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", difficulty);
+        assert_eq!(mv, "a1a8", "{:?}", difficulty);
+        let mv = best_move("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1", difficulty);
+        assert_eq!(mv, "a8a1", "{:?}", difficulty);
+    }
+}
+
+#[test]
+fn takes_hanging_piece() {
+    for difficulty in DIFFICULTIES {
+        let mv = best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", difficulty);
+        assert_eq!(mv, "d2d5", "{:?}", difficulty);
+        let mv = best_move("4k3/2n5/8/3Q4/8/8/8/4K3 b - - 0 1", difficulty);
+        assert_eq!(mv, "c7d5", "{:?}", difficulty);
+    }
+}
+
+#[test]
+fn card_and_event_choices_respect_the_limit() {
+    let mut board = Board::default();
+    board.white_cards = Card::all().filter(|card| *card != Card::Blank).collect();
+    let rules = Rules {
+        deck: DeckConfig::preset("mayhem").unwrap(),
+        choose_cards: true,
+        choose_events: true,
+    };
+    let limit = Duration::from_millis(50);
+    let start = Instant::now();
+    ai::choose_card(&board, &rules, Difficulty::Hard, limit);
+    for card in Card::all() {
+        ai::choose_event(&board, &rules, card, Difficulty::Hard, limit);
+    }
+    let calls = Card::all().count() as u32 + 1;
+    assert!(start.elapsed() < limit * calls + Duration::from_secs(1));
+}
+
+#[test]
+fn accepts_draw_only_when_worse() {
+    let rules = Rules::default();
+    let board = Board::default();
+    for color in [Color::White, Color::Black] {
+        assert!(!ai::accepts_draw(
+            &board,
+            &rules,
+            color,
+            Difficulty::Hard,
+            LIMIT
+        ));
+    }
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    assert!(ai::accepts_draw(
+        &board,
+        &rules,
+        Color::Black,
+        Difficulty::Hard,
+        LIMIT
+    ));
+    assert!(!ai::accepts_draw(
+        &board,
+        &rules,
+        Color::White,
+        Difficulty::Hard,
+        LIMIT
+    ));
+}
+
+// Rxe5 wins a pawn, unless Black can swap its knight and bishop so that Ng6 guards e5.
+const SWAP_THREAT: &str = "7k/7n/6b1/4p3/8/8/8/4R1K1 w - - 0 1";
+
+fn reply(black_cards: &[Card], weights: &[(Card, u32)], refill: Refill) -> String {
+    let mut board = Board::from_fen(SWAP_THREAT).unwrap();
+    board.black_cards = black_cards.to_vec();
+    let mut rules = Rules::default();
+    rules.deck.weights = weights.iter().copied().collect();
+    rules.deck.refill = refill;
+    // Normal searches to a fixed depth, well within the limit.
+    ai::best_move(&board, &rules, Difficulty::Normal, Duration::from_secs(30))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn known_opponent_card_changes_the_move() {
+    for refill in [Refill::WhenEmpty, Refill::Continuous] {
+        let weights = [(Card::Blank, 1)];
+        assert_eq!(reply(&[Card::Blank], &weights, refill), "e1e5");
+        assert_ne!(reply(&[Card::Swap], &weights, refill), "e1e5");
+        assert_ne!(reply(&[Card::Blank, Card::Swap], &weights, refill), "e1e5");
+        // Only the last card in hand is drawn next, whatever the deck holds.
+        let weights = [(Card::Swap, 1)];
+        assert_eq!(reply(&[Card::Swap, Card::Blank], &weights, refill), "e1e5");
+    }
+}
+
+// Kxf4 wins a knight, but leaves the a-pawn free to run to a2 and promote should Black draw a
+// pawn run; Bxa6 settles for the pawn instead. Hands are full, as Continuous refills keep them.
+const PAWN_RUN_THREAT: &str = "2B4k/8/p7/8/5n2/6K1/8/8 w - - 0 1";
+
+fn refill_reply(black_cards: &[Card], weights: &[(Card, u32)], refill: Refill) -> String {
+    let mut board = Board::from_fen(PAWN_RUN_THREAT).unwrap();
+    board.white_cards = vec![Card::Blank];
+    board.black_cards = black_cards.to_vec();
+    let mut rules = Rules::default();
+    rules.deck.weights = weights.iter().copied().collect();
+    rules.deck.refill = refill;
+    rules.deck.hand_size = black_cards.len();
+    ai::best_move(&board, &rules, Difficulty::Normal, Duration::from_secs(30))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn refilled_cards_follow_deck_weights() {
+    // Black's last card is the Blank it plays next move; the card after it is drawn fresh.
+    for refill in [Refill::WhenEmpty, Refill::Continuous] {
+        let reply = |weights: &[(Card, u32)]| refill_reply(&[Card::Blank], weights, refill);
+        assert_eq!(reply(&[(Card::Blank, 1)]), "g3f4");
+        assert_eq!(reply(&[(Card::PawnRun, 1)]), "c8a6");
+        assert_eq!(reply(&[(Card::Blank, 1000), (Card::PawnRun, 1)]), "g3f4");
+        assert_eq!(reply(&[(Card::Blank, 1), (Card::PawnRun, 1)]), "c8a6");
+        assert_eq!(reply(&[(Card::Blank, 0), (Card::PawnRun, 1)]), "c8a6");
+    }
+    // A second known card comes before any refill.
+    let weights = [(Card::PawnRun, 1)];
+    let hand = [Card::Blank, Card::Blank];
+    assert_eq!(refill_reply(&hand, &weights, Refill::WhenEmpty), "g3f4");
+    assert_eq!(refill_reply(&hand, &weights, Refill::Continuous), "g3f4");
+    let hand = [Card::PawnRun, Card::Blank];
+    assert_eq!(refill_reply(&hand, &weights, Refill::Continuous), "c8a6");
+}
+
+#[actix::test]
+async fn ai_answers_draw_offer_and_moves() {
+    let config = server::Config {
+        ai: Some(Difficulty::Easy),
+        ..server::Config::default()
+    };
+    let server = common::start(config);
+    let sink = Sink::default();
+    let id = common::connect(&server, &sink).await;
+    let request = |request| ClientRequest { id, request };
+    let result = server.send(request(Request::OfferDraw)).await.unwrap();
+    assert_eq!(result, Ok(()));
+    assert_eq!(sink.state()["draw_offer"], "White");
+
+    let result = server.send(request(Request::Uci("e2e4".to_string()))).await;
+    assert_eq!(result.unwrap(), Ok(()));
+    for _ in 0..50 {
+        actix::clock::sleep(Duration::from_millis(100)).await;
+        let state = sink.state();
+        if state["plies"] == 2 {
+            assert_eq!(state["draw_offer"], Value::Null);
+            assert_eq!(state["state"], "Normal");
+            return;
+        }
+    }
+    panic!("the AI did not answer");
+}
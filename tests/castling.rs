@@ -0,0 +1,131 @@
+use chess::{
+    chess::{Board, Card, Event, Location},
+    effect::{self, CardEffect, CustomEvent},
+};
+
+fn loc(s: &str) -> Location {
+    s.parse().unwrap()
+}
+
+fn can_castle(board: &Board, to: &str) -> bool {
+    board.find_move(loc("e1"), loc(to), None).is_some()
+}
+
+fn castles(board: &Board) -> String {
+    board.to_string().split(' ').nth(2).unwrap().to_string()
+}
+
+#[test]
+fn queenside_requires_empty_b_file() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1").unwrap();
+    assert!(!can_castle(&board, "c1"));
+    assert!(can_castle(&board, "g1"));
+}
+
+#[test]
+fn cannot_castle_through_attacked_square() {
+    let board = Board::from_fen("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    assert!(!can_castle(&board, "g1"));
+    assert!(can_castle(&board, "c1"));
+
+    let board = Board::from_fen("3rk3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    assert!(!can_castle(&board, "c1"));
+    assert!(can_castle(&board, "g1"));
+}
+
+#[test]
+fn cannot_castle_out_of_check() {
+    let board = Board::from_fen("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    assert!(!can_castle(&board, "g1"));
+    assert!(!can_castle(&board, "c1"));
+}
+
+#[test]
+fn rook_must_stand_on_corner() {
+    let mut board = Board::from_fen("r3k3/8/8/8/8/8/8/R3K1NR w KQ - 0 1").unwrap();
+    let rook = board.piece(loc("h1"));
+
+    board.set_piece(loc("h1"), board.piece(loc("g1")));
+    board.set_piece(loc("g1"), None);
+    assert!(!can_castle(&board, "g1"));
+
+    board.set_piece(loc("h1"), board.piece(loc("a8")));
+    assert!(!can_castle(&board, "g1"));
+
+    board.set_piece(loc("h1"), rook);
+    assert!(can_castle(&board, "g1"));
+}
+
+#[test]
+fn fen_without_rook_has_no_castling_rights() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w KQ - 0 1").unwrap();
+    assert_eq!(castles(&board), "K");
+    assert!(!can_castle(&board, "c1"));
+}
+
+#[test]
+fn swap_revokes_castling() {
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3KB1R w KQ - 0 1").unwrap();
+    let event = board
+        .event_candidates(Card::Swap)
+        .into_iter()
+        .find(|ev| matches!(ev, Event::Swap(l1, l2) if [*l1, *l2].contains(&loc("h1"))))
+        .unwrap();
+    assert!(board.replay_card(Card::Swap, Some(event)));
+    assert_eq!(castles(&board), "Q");
+    assert!(!can_castle(&board, "g1"));
+    assert!(can_castle(&board, "c1"));
+}
+
+#[test]
+fn rotate_does_not_grant_castling() {
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    let event = Event::Rotate(loc("a1"), loc("h1"));
+    assert!(board.replay_card(Card::Rotate, Some(event)));
+    assert_eq!(castles(&board), "-");
+    assert!(!can_castle(&board, "g1"));
+    assert!(!can_castle(&board, "c1"));
+}
+
+struct RookLift;
+
+impl CardEffect for RookLift {
+    fn id(&self) -> &'static str {
+        "RookLift"
+    }
+
+    fn name(&self) -> &'static str {
+        "RookLift"
+    }
+
+    fn description(&self) -> &'static str {
+        "The rook on h1 steps up to h2."
+    }
+
+    fn candidates(&self, board: &Board) -> Vec<CustomEvent> {
+        if board.piece(loc("h1")).is_some_and(|p| p.is_rook()) && board.is_empty(loc("h2")) {
+            self.event(&[loc("h1"), loc("h2")]).into_iter().collect()
+        } else {
+            vec![]
+        }
+    }
+
+    fn apply(&self, board: &mut Board, event: &CustomEvent) {
+        let &[from, to] = event.locations() else {
+            return;
+        };
+        let piece = board.piece(from);
+        board.set_piece(from, None);
+        board.set_piece(to, piece);
+    }
+}
+
+#[test]
+fn custom_effect_moving_rook_revokes_castling() {
+    let card = effect::register(RookLift);
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let event = board.event_candidates(card).pop().unwrap();
+    assert!(board.replay_card(card, Some(event)));
+    assert_eq!(castles(&board), "Q");
+    assert!(!can_castle(&board, "g1"));
+}
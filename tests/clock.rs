@@ -0,0 +1,45 @@
+use chess::{
+    chess::Color,
+    clock::{Bonus, Clock, TimeControl},
+};
+use std::time::{Duration, Instant};
+
+fn clock(bonus: Option<Bonus>) -> Clock {
+    Clock::new(TimeControl {
+        base: Duration::from_secs(60),
+        bonus,
+    })
+}
+
+#[test]
+fn fischer_adds_increment() {
+    let mut clock = clock(Some(Bonus::Fischer(Duration::from_secs(2))));
+    let start = Instant::now();
+    clock.punch(Color::Black, start);
+    clock.punch(Color::White, start + Duration::from_secs(5));
+    let now = start + Duration::from_secs(5);
+    assert_eq!(clock.remaining(Color::White, now), Duration::from_secs(57));
+}
+
+#[test]
+fn bronstein_refunds_up_to_delay() {
+    let mut clock = clock(Some(Bonus::Bronstein(Duration::from_secs(3))));
+    let start = Instant::now();
+    clock.punch(Color::Black, start);
+    clock.punch(Color::White, start + Duration::from_secs(2));
+    clock.punch(Color::Black, start + Duration::from_secs(2));
+    clock.punch(Color::White, start + Duration::from_secs(12));
+    let now = start + Duration::from_secs(12);
+    assert_eq!(clock.remaining(Color::White, now), Duration::from_secs(53));
+}
+
+#[test]
+fn huge_increment_saturates() {
+    let mut clock = clock(Some(Bonus::Fischer(Duration::MAX)));
+    let start = Instant::now();
+    clock.punch(Color::Black, start);
+    clock.punch(Color::White, start + Duration::from_secs(1));
+    let now = start + Duration::from_secs(1);
+    assert_eq!(clock.remaining(Color::White, now), Duration::MAX);
+    assert_eq!(clock.flagged(now), None);
+}
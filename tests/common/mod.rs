@@ -0,0 +1,52 @@
+#![allow(dead_code)]
+
+use actix::prelude::*;
+use chess::{
+    lobby::Lobby,
+    record::GameRecord,
+    server::{self, Connect, Server},
+};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+// Keeps the last state the server sent to one session.
+#[derive(Default, Clone)]
+pub struct Sink(Arc<Mutex<Value>>);
+
+impl Sink {
+    pub fn state(&self) -> Value {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Actor for Sink {
+    type Context = Context<Self>;
+}
+
+impl Handler<server::Message> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, msg: server::Message, _: &mut Context<Self>) {
+        *self.0.lock().unwrap() = serde_json::to_value(msg).unwrap();
+    }
+}
+
+pub fn start(config: server::Config) -> Addr<Server> {
+    Server::new("test".to_string(), Lobby::default().start(), config).start()
+}
+
+pub async fn connect(server: &Addr<Server>, sink: &Sink) -> usize {
+    let connect = Connect {
+        addr: sink.clone().start().recipient(),
+        admin: false,
+        name: None,
+    };
+    server.send(connect).await.unwrap()
+}
+
+pub fn play(record: &mut GameRecord, uci: &str) {
+    let mut board = record.board().clone();
+    let mv = board.parse_uci(uci).unwrap();
+    board.move_piece(&mv);
+    record.push(board);
+}
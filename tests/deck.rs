@@ -0,0 +1,43 @@
+use chess::{chess::Card, deck::DeckConfig};
+
+#[test]
+fn presets_are_valid() {
+    for name in DeckConfig::PRESETS {
+        assert!(DeckConfig::preset(name).unwrap().is_valid(), "{}", name);
+    }
+}
+
+#[test]
+fn hand_size_is_bounded() {
+    let mut deck = DeckConfig::standard();
+    deck.hand_size = 0;
+    assert!(!deck.is_valid());
+    deck.hand_size = DeckConfig::MAX_HAND_SIZE;
+    assert!(deck.is_valid());
+    deck.hand_size = 1_000_000_000_000_000;
+    assert!(!deck.is_valid());
+
+    let mut deck = DeckConfig::standard();
+    deck.opening_hand = vec![Card::Blank; DeckConfig::MAX_HAND_SIZE + 1];
+    assert!(!deck.is_valid());
+}
+
+#[test]
+fn weights_are_bounded() {
+    let mut deck = DeckConfig::standard();
+    deck.weights.values_mut().for_each(|w| *w = 0);
+    assert!(!deck.is_valid());
+    deck.weights.values_mut().for_each(|w| *w = u32::MAX);
+    assert!(!deck.is_valid());
+    deck.weights
+        .insert(Card::Blank, DeckConfig::MAX_TOTAL_WEIGHT as u32);
+    deck.weights.retain(|card, _| *card == Card::Blank);
+    assert!(deck.is_valid());
+}
+
+#[test]
+fn oversized_deck_body_is_rejected() {
+    let body = r#"{"name":"huge","weights":{"Blank":1},"hand_size":1000000000000000,"refill":"WhenEmpty","opening_hand":[]}"#;
+    let deck: DeckConfig = serde_json::from_str(body).unwrap();
+    assert!(!deck.is_valid());
+}
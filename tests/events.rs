@@ -0,0 +1,114 @@
+use chess::chess::{Board, Card, Event, Location};
+
+const CARDS: [Card; 3] = [Card::DemoteQueen, Card::Freeze, Card::PushBack];
+
+const POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 2 3",
+    "4k3/8/8/8/1q6/8/8/4K3 w - - 0 1",
+    "k7/8/8/3p3p/4B3/8/8/K7 w - - 0 1",
+    "7k/8/3bp3/3P4/8/2n5/7r/K7 w - - 0 1",
+];
+
+fn loc(s: &str) -> Location {
+    s.parse().unwrap()
+}
+
+fn ev(s: &str) -> Event {
+    s.parse().unwrap()
+}
+
+// Whether the side to move could capture the other king.
+fn exposes_king(board: &Board) -> bool {
+    let fen = board.to_string();
+    let mut fields: Vec<_> = fen.split(' ').collect();
+    fields[1] = if fields[1] == "w" { "b" } else { "w" };
+    fields[3] = "-";
+    Board::from_fen(&fields.join(" "))
+        .unwrap()
+        .get_check()
+        .is_some()
+}
+
+#[test]
+fn candidates_keep_kings_safe_and_moves_available() {
+    for fen in POSITIONS {
+        for color in ["w", "b"] {
+            let board = Board::from_fen(&fen.replacen(" w ", &format!(" {} ", color), 1));
+            let Ok(board) = board else {
+                continue;
+            };
+            for card in CARDS {
+                for ev in board.event_candidates(card) {
+                    let after = board.event_applied(ev);
+                    assert!(!exposes_king(&after), "{} {}", fen, ev);
+                    assert!(!after.all_possible_moves().is_empty(), "{} {}", fen, ev);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn demote_queen_makes_a_rook() {
+    let board = Board::from_fen(POSITIONS[2]).unwrap();
+    assert_eq!(
+        board.event_candidates(Card::DemoteQueen),
+        [ev("DemoteQueen b4")]
+    );
+    let after = board.event_applied(ev("DemoteQueen b4"));
+    assert_eq!(after.to_string(), "4k3/8/8/8/1r6/8/8/4K3 w - - 0 1");
+    // The queen gave check along the diagonal; the rook does not.
+    assert!(board.get_check().is_some());
+    assert!(after.get_check().is_none());
+}
+
+#[test]
+fn push_back_cannot_discover_check() {
+    let board = Board::from_fen(POSITIONS[3]).unwrap();
+    assert_eq!(
+        board.event_candidates(Card::PushBack),
+        [ev("PushBack h5 h6")]
+    );
+}
+
+#[test]
+fn push_back_cannot_take_the_last_move() {
+    let board = Board::from_fen(POSITIONS[4]).unwrap();
+    assert_eq!(board.legal_moves().len(), 1);
+    assert!(board.event_candidates(Card::PushBack).is_empty());
+}
+
+#[test]
+fn frozen_piece_cannot_move() {
+    let mut board = Board::from_fen("k7/2K4p/2N5/8/8/8/P7/7n w - - 0 1").unwrap();
+    assert!(board
+        .event_candidates(Card::Freeze)
+        .contains(&ev("Freeze h1")));
+    board = board.event_applied(ev("Freeze h1"));
+    board.move_piece(&board.find_move(loc("a2"), loc("a3"), None).unwrap());
+    assert_eq!(board.frozen(), Some(loc("h1")));
+    let movers: Vec<_> = board
+        .all_possible_moves()
+        .into_iter()
+        .map(|(loc, _)| loc)
+        .collect();
+    assert_eq!(movers, [loc("h7")]);
+}
+
+#[test]
+fn frozen_piece_moves_when_it_is_the_only_mover() {
+    let mut board = Board::from_fen("k7/2K5/2N5/8/8/8/P7/7n w - - 0 1").unwrap();
+    assert_eq!(board.event_candidates(Card::Freeze), [ev("Freeze h1")]);
+    board = board.event_applied(ev("Freeze h1"));
+    board.move_piece(&board.find_move(loc("a2"), loc("a3"), None).unwrap());
+    assert_eq!(board.frozen(), Some(loc("h1")));
+    let moves = board.all_possible_moves();
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].0, loc("h1"));
+    assert_eq!(moves[0].1.len(), 2);
+
+    let mv = board.find_move(loc("h1"), loc("g3"), None).unwrap();
+    board.move_piece(&mv);
+    assert_eq!(board.frozen(), None);
+}
@@ -0,0 +1,107 @@
+use chess::chess::{Board, FenError};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn round_trip(fen: &str) {
+    let board = Board::from_fen(fen).unwrap();
+    assert_eq!(board.to_string(), fen);
+}
+
+#[test]
+fn start_position() {
+    assert_eq!(Board::default().to_string(), START);
+    round_trip(START);
+    assert_eq!(START.parse::<Board>().unwrap().to_string(), START);
+}
+
+#[test]
+fn castling_en_passant_and_counters() {
+    round_trip("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3");
+    round_trip("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3");
+    round_trip("r3k2r/8/8/8/8/8/8/R3K2R b Qk - 17 42");
+    round_trip("4k3/8/8/8/8/8/8/4K3 w - - 99 120");
+}
+
+#[test]
+fn short_fen_defaults_counters() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(board.to_string(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    assert_eq!(board.plies, 1);
+}
+
+fn error(fen: &str) -> FenError {
+    Board::from_fen(fen).unwrap_err()
+}
+
+#[test]
+fn field_count() {
+    assert_eq!(error(""), FenError::FieldCount);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w -"), FenError::FieldCount);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0"), FenError::FieldCount);
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 x"),
+        FenError::FieldCount
+    );
+}
+
+#[test]
+fn placement() {
+    assert_eq!(error("4k3/8/8/8/8/8/4K3 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K2 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K4 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4X3 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/08/8/8/8/4K3 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/8/8/8/8/8 w - - 0 1"), FenError::Placement);
+    assert_eq!(error("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"), FenError::Placement);
+}
+
+#[test]
+fn active_color() {
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+        FenError::ActiveColor
+    );
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 W - - 0 1"),
+        FenError::ActiveColor
+    );
+}
+
+#[test]
+fn castling() {
+    assert_eq!(
+        error("r3k2r/8/8/8/8/8/8/R3K2R w KX - 0 1"),
+        FenError::Castling
+    );
+    assert_eq!(
+        error("r3k2r/8/8/8/8/8/8/R3K2R w KK - 0 1"),
+        FenError::Castling
+    );
+}
+
+#[test]
+fn en_passant() {
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - e9 0 1"), FenError::EnPassant);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - e3 0 1"), FenError::EnPassant);
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 b - e6 0 1"), FenError::EnPassant);
+}
+
+#[test]
+fn counters() {
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+        FenError::HalfmoveClock
+    );
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 w - - -1 1"),
+        FenError::HalfmoveClock
+    );
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 w - - 0 x"),
+        FenError::FullmoveNumber
+    );
+    assert_eq!(
+        error("4k3/8/8/8/8/8/8/4K3 w - - 0 0"),
+        FenError::FullmoveNumber
+    );
+}
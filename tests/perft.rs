@@ -0,0 +1,103 @@
+use chess::chess::Board;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_MIRRORED: &str =
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn check(fen: &str, expected: &[(u32, u64)]) {
+    let board = Board::from_fen(fen).unwrap();
+    for &(depth, nodes) in expected {
+        assert_eq!(board.perft(depth), nodes, "{} at depth {}", fen, depth);
+    }
+}
+
+#[test]
+fn start_position() {
+    check(START, &[(0, 1), (1, 20), (2, 400), (3, 8902), (4, 197281)]);
+}
+
+#[test]
+fn default_board_matches_start_fen() {
+    assert_eq!(Board::default().perft(3), 8902);
+}
+
+#[test]
+fn kiwipete() {
+    check(KIWIPETE, &[(1, 48), (2, 2039), (3, 97862)]);
+}
+
+#[test]
+fn position_3() {
+    check(
+        POSITION_3,
+        &[(1, 14), (2, 191), (3, 2812), (4, 43238), (5, 674624)],
+    );
+}
+
+#[test]
+fn position_4() {
+    check(POSITION_4, &[(1, 6), (2, 264), (3, 9467), (4, 422333)]);
+    check(
+        POSITION_4_MIRRORED,
+        &[(1, 6), (2, 264), (3, 9467), (4, 422333)],
+    );
+}
+
+#[test]
+fn position_5() {
+    check(POSITION_5, &[(1, 44), (2, 1486), (3, 62379), (4, 2103487)]);
+}
+
+#[test]
+fn position_6() {
+    check(POSITION_6, &[(1, 46), (2, 2079), (3, 89890)]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    let divide = board.divide(2);
+    assert_eq!(divide.len(), 48);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+}
+
+#[test]
+#[ignore]
+fn start_position_deep() {
+    check(START, &[(5, 4865609), (6, 119060324)]);
+}
+
+#[test]
+#[ignore]
+fn kiwipete_deep() {
+    check(KIWIPETE, &[(4, 4085603)]);
+}
+
+#[test]
+#[ignore]
+fn position_3_deep() {
+    check(POSITION_3, &[(6, 11030083)]);
+}
+
+#[test]
+#[ignore]
+fn position_4_deep() {
+    check(POSITION_4, &[(5, 15833292)]);
+}
+
+#[test]
+#[ignore]
+fn position_5_deep() {
+    check(POSITION_5, &[(5, 89941194)]);
+}
+
+#[test]
+#[ignore]
+fn position_6_deep() {
+    check(POSITION_6, &[(4, 3894594)]);
+}
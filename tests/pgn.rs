@@ -0,0 +1,122 @@
+mod common;
+
+use chess::{
+    chess::{Board, Card},
+    pgn::{self, Headers, PgnError},
+    record::GameRecord,
+};
+use common::play;
+
+fn swap_game() -> GameRecord {
+    let mut record = GameRecord::default();
+    play(&mut record, "e2e4");
+    let board = record.board_mut();
+    let event = board.event_candidates(Card::Swap)[0];
+    assert!(board.replay_card(Card::Swap, Some(event)));
+    record
+}
+
+#[test]
+fn card_comment_names_the_card_once() {
+    let record = swap_game();
+    let event = record.board().last_event.unwrap();
+    let pgn = pgn::export(&record, &Headers::default());
+    assert!(pgn.contains(&format!("{{[%card {}]}}", event)));
+    assert!(!pgn.contains("Swap Swap"));
+
+    let imported = pgn::import(&pgn).unwrap();
+    assert_eq!(imported.board().to_string(), record.board().to_string());
+    assert_eq!(imported.board().last_event, Some(event));
+}
+
+#[test]
+fn card_comment_is_the_event_alone() {
+    let event = swap_game().board().last_event.unwrap();
+    let index = Card::all().position(|card| card == Card::Swap).unwrap();
+    for comment in [format!("Swap {}", event), format!("{} {}", index, event)] {
+        let pgn = format!("1. e4 {{[%card {}]}} *", comment);
+        assert!(matches!(
+            pgn::import(&pgn),
+            Err(PgnError::InvalidCard(1, _))
+        ));
+    }
+}
+
+#[test]
+fn blank_card_comment() {
+    let imported = pgn::import("1. e4 {[%card Blank]} e5 *").unwrap();
+    assert_eq!(imported.plies()[0].card, Some(Card::Blank));
+    assert_eq!(imported.board().last_card, None);
+    let pgn = pgn::export(&imported, &Headers::default());
+    assert!(pgn.contains("1. e4 {[%card Blank]} 1... e5 *"));
+    assert_eq!(
+        pgn::import(&pgn).unwrap().board().to_string(),
+        Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2")
+            .unwrap()
+            .to_string()
+    );
+}
+
+fn fen_after(pgn: &str) -> String {
+    pgn::import(pgn).unwrap().board().to_string()
+}
+
+#[test]
+fn castling_with_zeros() {
+    let fen = "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4";
+    assert_eq!(fen_after("1. e4 e5 2. Nf3 Nf6 3. Bc4 Bc5 4. 0-0 *"), fen);
+    assert_eq!(fen_after("1. e4 e5 2. Nf3 Nf6 3. Bc4 Bc5 4. O-O *"), fen);
+    assert_eq!(fen_after("1.e4 e5 2.Nf3 Nf6 3.Bc4 Bc5 4.0-0 *"), fen);
+}
+
+#[test]
+fn queenside_castling() {
+    let moves = "1. d4 d5 2. Nc3 Nc6 3. Bf4 Bf5 4. Qd2 Qd7";
+    let fen = fen_after(&format!("{} 5. O-O-O O-O-O *", moves));
+    assert!(fen.starts_with("2kr1bnr/"));
+    assert!(fen.contains("/2KR1BNR w - - "));
+    assert_eq!(fen_after(&format!("{} 5. 0-0-0 0-0-0 *", moves)), fen);
+    assert!(pgn::import(&format!("{} 5. O-O *", moves)).is_err());
+}
+
+#[test]
+fn nags_and_annotations() {
+    let fen = fen_after("1. e4 e5 2. Nf3 *");
+    assert_eq!(fen_after("1. e4! $1 e5?! $6 2. Nf3!! $3 *"), fen);
+    assert_eq!(fen_after("1. e4 e5 ; a comment\n2. Nf3 {quiet} *"), fen);
+    assert_eq!(fen_after("1. e4 1... e5 2. Nf3 *"), fen);
+}
+
+#[test]
+fn variations_are_skipped() {
+    let fen = fen_after("1. e4 e5 2. Nf3 *");
+    assert_eq!(
+        fen_after("1. e4 e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 {[%card Blank]} *"),
+        fen
+    );
+    assert!(pgn::import("1. e4 (1. d4 *").is_err());
+}
+
+#[test]
+fn illegal_moves_and_cards_report_the_ply() {
+    match pgn::import("1. e4 e5 2. Ke3 *") {
+        Err(pgn::PgnError::IllegalMove(3, san)) => assert_eq!(san, "Ke3"),
+        other => panic!("{:?}", other.map(|r| r.cursor())),
+    }
+    match pgn::import("1. e4 {[%card Swap a1 a2]} *") {
+        Err(pgn::PgnError::InvalidCard(1, _)) => {}
+        other => panic!("{:?}", other.map(|r| r.cursor())),
+    }
+    match pgn::import("{[%card Blank]} 1. e4 *") {
+        Err(pgn::PgnError::InvalidCard(0, _)) => {}
+        other => panic!("{:?}", other.map(|r| r.cursor())),
+    }
+}
+
+#[test]
+fn result_concludes_game() {
+    let record = pgn::import("[Result \"0-1\"]\n\n1. e4 e5 0-1").unwrap();
+    let board = record.board();
+    assert!(board.is_game_over());
+    assert!(pgn::import("1. e4 * e5").is_err());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use chess::{
+    chess::{Board, GameState},
+    record::GameRecord,
+};
+use common::play;
+
+fn state(record: &GameRecord) -> GameState {
+    let board = record.board();
+    board.game_state(&board.all_possible_moves())
+}
+
+fn fen(record: &GameRecord) -> String {
+    record.board().to_string()
+}
+
+#[test]
+fn undo_redo_and_goto() {
+    let mut record = GameRecord::default();
+    for uci in ["e2e4", "e7e5", "g1f3"] {
+        play(&mut record, uci);
+    }
+    let end = fen(&record);
+    assert_eq!(record.cursor(), 3);
+
+    assert!(record.undo());
+    assert_eq!(record.cursor(), 2);
+    assert_eq!(
+        fen(&record),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+    );
+    assert!(record.redo());
+    assert_eq!(fen(&record), end);
+    assert!(!record.redo());
+
+    assert!(record.goto(0));
+    assert_eq!(fen(&record), Board::default().to_string());
+    assert!(!record.undo());
+    assert_eq!(record.cursor(), 0);
+    assert!(!record.goto(4));
+    assert!(record.goto(3));
+    assert_eq!(fen(&record), end);
+}
+
+#[test]
+fn push_after_undo_truncates_redo_branch() {
+    let mut record = GameRecord::default();
+    for uci in ["e2e4", "e7e5", "g1f3"] {
+        play(&mut record, uci);
+    }
+    record.undo();
+    record.undo();
+    play(&mut record, "c7c5");
+    assert_eq!(record.cursor(), 2);
+    assert!(!record.redo());
+    assert!(!record.goto(3));
+    assert_eq!(record.plies().len(), 2);
+    assert_eq!(record.history().len(), 2);
+}
+
+#[test]
+fn repetition_survives_undo_and_redo() {
+    let mut record = GameRecord::default();
+    for _ in 0..2 {
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            play(&mut record, uci);
+        }
+    }
+    assert_eq!(state(&record), GameState::Repetition);
+    record.undo();
+    assert_eq!(state(&record), GameState::Normal);
+    record.redo();
+    assert_eq!(state(&record), GameState::Repetition);
+    record.goto(4);
+    record.goto(8);
+    assert_eq!(state(&record), GameState::Repetition);
+}
@@ -0,0 +1,101 @@
+use chess::chess::{Board, SanError};
+
+fn from_fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn san(board: &Board, uci: &str) -> String {
+    board.san(&board.parse_uci(uci).unwrap())
+}
+
+fn parse(board: &Board, san: &str) -> Result<String, SanError> {
+    board.parse_san(san).map(|mv| mv.to_string())
+}
+
+// Queens on h4, e1 and h1 can all reach e4.
+const THREE_QUEENS: &str = "1k6/8/8/8/7Q/8/K7/4Q2Q w - - 0 1";
+
+#[test]
+fn disambiguation() {
+    let board = from_fen(THREE_QUEENS);
+    assert_eq!(san(&board, "e1e4"), "Qee4");
+    assert_eq!(san(&board, "h4e4"), "Q4e4");
+    assert_eq!(san(&board, "h1e4"), "Qh1e4");
+
+    assert_eq!(parse(&board, "Qee4").unwrap(), "e1e4");
+    assert_eq!(parse(&board, "Q4e4").unwrap(), "h4e4");
+    assert_eq!(parse(&board, "Qh1e4").unwrap(), "h1e4");
+    assert_eq!(parse(&board, "Qh1-e4").unwrap(), "h1e4");
+    assert_eq!(parse(&board, "Qe4"), Err(SanError::Ambiguous));
+    assert_eq!(parse(&board, "Qhe4"), Err(SanError::Ambiguous));
+    assert_eq!(parse(&board, "Q1e4"), Err(SanError::Ambiguous));
+}
+
+#[test]
+fn knights_on_the_same_rank() {
+    let board = from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert_eq!(san(&board, "b1d2"), "Nbd2");
+    assert_eq!(san(&board, "f1g3"), "Ng3");
+    assert_eq!(parse(&board, "Nbd2").unwrap(), "b1d2");
+    assert_eq!(parse(&board, "Nd2"), Err(SanError::Ambiguous));
+}
+
+#[test]
+fn check_and_mate_suffixes() {
+    let mut board = Board::default();
+    for uci in ["f2f3", "e7e5", "g2g4"] {
+        let mv = board.parse_uci(uci).unwrap();
+        board.move_piece(&mv);
+    }
+    assert_eq!(san(&board, "d8h4"), "Qh4#");
+    assert_eq!(parse(&board, "Qh4#").unwrap(), "d8h4");
+    assert_eq!(parse(&board, "Qh4").unwrap(), "d8h4");
+    assert_eq!(parse(&board, "Qh4+!?").unwrap(), "d8h4");
+
+    let board = from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(san(&board, "a1a8"), "Ra8+");
+    assert_eq!(san(&board, "a1a7"), "Ra7");
+}
+
+#[test]
+fn promotions() {
+    let board = from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(san(&board, "a7a8q"), "a8=Q");
+    assert_eq!(san(&board, "a7b8n"), "axb8=N");
+    assert_eq!(san(&board, "a7b8q"), "axb8=Q+");
+    for (text, uci) in [
+        ("a8=Q", "a7a8q"),
+        ("a8Q", "a7a8q"),
+        ("a8=R", "a7a8r"),
+        ("axb8=N", "a7b8n"),
+        ("axb8=Q+", "a7b8q"),
+    ] {
+        assert_eq!(parse(&board, text).unwrap(), uci, "{}", text);
+    }
+    assert_eq!(parse(&board, "a8"), Err(SanError::IllegalMove));
+    assert_eq!(parse(&board, "a8=K"), Err(SanError::Syntax));
+}
+
+#[test]
+fn castling() {
+    let board = from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(san(&board, "e1g1"), "O-O");
+    assert_eq!(san(&board, "e1c1"), "O-O-O");
+    assert_eq!(parse(&board, "O-O").unwrap(), "e1g1");
+    assert_eq!(parse(&board, "O-O-O").unwrap(), "e1c1");
+    assert_eq!(parse(&board, "0-0-0").unwrap(), "e1c1");
+
+    let board = from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1");
+    assert_eq!(parse(&board, "O-O-O"), Err(SanError::IllegalMove));
+}
+
+#[test]
+fn pawn_captures_and_en_passant() {
+    let board = from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(san(&board, "e5d6"), "exd6");
+    assert_eq!(parse(&board, "exd6").unwrap(), "e5d6");
+    assert_eq!(parse(&board, "exd6 e.p.").unwrap(), "e5d6");
+    assert_eq!(parse(&board, "e6").unwrap(), "e5e6");
+    assert_eq!(parse(&board, "Zf3"), Err(SanError::Syntax));
+    assert_eq!(parse(&board, "e"), Err(SanError::Syntax));
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use actix::Addr;
+use chess::{
+    deck::DeckConfig,
+    pgn,
+    server::{self, ClientRequest, ExportPgn, Request, Server},
+};
+use common::Sink;
+use serde_json::Value;
+use std::time::Duration;
+
+struct Game {
+    server: Addr<Server>,
+    white: (usize, Sink),
+    black: (usize, Sink),
+}
+
+impl Game {
+    async fn start(config: server::Config) -> Self {
+        let server = common::start(config);
+        let (white, black) = (Sink::default(), Sink::default());
+        let white = (common::connect(&server, &white).await, white);
+        let black = (common::connect(&server, &black).await, black);
+        Self {
+            server,
+            white,
+            black,
+        }
+    }
+
+    async fn pgn(&self) -> String {
+        self.server.send(ExportPgn).await.unwrap()
+    }
+
+    // Plays the first legal move of the current position `plies` times.
+    async fn play(&self, plies: usize) {
+        for _ in 0..plies {
+            let record = pgn::import(&self.pgn().await).unwrap();
+            let board = record.board();
+            if board.is_game_over() {
+                return;
+            }
+            let mv = &board.legal_moves()[0];
+            let id = if board.active.is_white() {
+                self.white.0
+            } else {
+                self.black.0
+            };
+            let request = Request::Uci(mv.to_string());
+            let result = self.server.send(ClientRequest { id, request }).await;
+            assert_eq!(result.unwrap(), Ok(()));
+        }
+    }
+
+    async fn hands(&self) -> Vec<Value> {
+        actix::clock::sleep(Duration::from_millis(10)).await;
+        [&self.white.1, &self.black.1]
+            .iter()
+            .map(|sink| sink.state()["my_cards"].clone())
+            .collect()
+    }
+}
+
+fn config() -> server::Config {
+    server::Config {
+        deck: DeckConfig::preset("mayhem").unwrap(),
+        seed: Some(42),
+        ..server::Config::default()
+    }
+}
+
+#[actix::test]
+async fn same_seed_same_cards_and_events() {
+    let first = Game::start(config()).await;
+    let second = Game::start(config()).await;
+    first.play(16).await;
+    second.play(16).await;
+
+    let pgn = first.pgn().await;
+    assert!(pgn.contains("[Seed \"42\"]"));
+    assert!(pgn.contains("[Deck \"mayhem\"]"));
+    assert!(pgn.contains("[%card"));
+    assert_eq!(pgn, second.pgn().await);
+    assert_eq!(first.hands().await, second.hands().await);
+}
+
+#[actix::test]
+async fn export_import_continue() {
+    let original = Game::start(config()).await;
+    original.play(10).await;
+    let record = pgn::import(&original.pgn().await).unwrap();
+    assert_eq!(record.seed(), Some(42));
+    assert_eq!(record.deck(), DeckConfig::preset("mayhem").as_ref());
+
+    let imported = Game::start(server::Config {
+        record: Some(record),
+        ..server::Config::default()
+    })
+    .await;
+    assert_eq!(original.hands().await, imported.hands().await);
+    original.play(10).await;
+    imported.play(10).await;
+    assert_eq!(original.pgn().await, imported.pgn().await);
+    assert_eq!(original.hands().await, imported.hands().await);
+}
+
+// The standard deck deals several Blanks to a hand, so a card taken from the end of the hand
+// has an identical copy further in front.
+#[actix::test]
+async fn duplicate_cards_redeal_in_order() {
+    let mut duplicates = 0;
+    for seed in 0..10 {
+        let original = Game::start(server::Config {
+            seed: Some(seed),
+            ..server::Config::default()
+        })
+        .await;
+        original.play(4).await;
+        for _ in 0..10 {
+            original.play(1).await;
+            let hands = original.hands().await;
+            duplicates += hands
+                .iter()
+                .filter(|hand| {
+                    let hand = hand.as_array().unwrap();
+                    (1..hand.len()).any(|i| hand[..i].contains(&hand[i]))
+                })
+                .count();
+            let record = pgn::import(&original.pgn().await).unwrap();
+            let imported = Game::start(server::Config {
+                record: Some(record),
+                ..server::Config::default()
+            })
+            .await;
+            assert_eq!(hands, imported.hands().await, "seed {}", seed);
+        }
+    }
+    assert!(duplicates > 0);
+}
+
+#[actix::test]
+async fn custom_deck_round_trips() {
+    let mut deck = DeckConfig::preset("calm").unwrap();
+    deck.name = "my \"deck\"".to_string();
+    deck.hand_size = 4;
+    let game = Game::start(server::Config {
+        deck: deck.clone(),
+        ..config()
+    })
+    .await;
+    game.play(4).await;
+    let record = pgn::import(&game.pgn().await).unwrap();
+    assert_eq!(record.deck(), Some(&deck));
+}
+
+#[actix::test]
+async fn unreproducible_games_have_no_seed() {
+    for (choose_cards, choose_events) in [(true, false), (false, true)] {
+        let game = Game::start(server::Config {
+            choose_cards,
+            choose_events,
+            ..config()
+        })
+        .await;
+        assert!(!game.pgn().await.contains("[Seed"));
+    }
+
+    let original = Game::start(config()).await;
+    original.play(10).await;
+    let mut record = pgn::import(&original.pgn().await).unwrap();
+    record.set_seed(7);
+    let game = Game::start(server::Config {
+        record: Some(record),
+        ..server::Config::default()
+    })
+    .await;
+    assert!(!game.pgn().await.contains("[Seed"));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use chess::server::{self, ClientRequest, Request, RequestError};
+use common::Sink;
+
+#[actix::test]
+async fn invalid_card_keeps_the_card_phase() {
+    for choose_events in [false, true] {
+        let config = server::Config {
+            choose_cards: true,
+            choose_events,
+            ..server::Config::default()
+        };
+        let server = common::start(config);
+        let sink = Sink::default();
+        let ids = [
+            common::connect(&server, &sink).await,
+            common::connect(&server, &sink).await,
+        ];
+        let request = |id, request| ClientRequest { id, request };
+        let result = server.send(request(ids[0], Request::Uci("e2e4".to_string())));
+        assert_eq!(result.await.unwrap(), Ok(()));
+
+        let result = server.send(request(ids[1], Request::PlayCard(99))).await;
+        assert_eq!(result.unwrap(), Err(RequestError::InvalidCard));
+        let result = server.send(request(ids[1], Request::Uci("e7e5".to_string())));
+        assert_eq!(result.await.unwrap(), Err(RequestError::CardPending));
+
+        let result = server.send(request(ids[1], Request::PlayCard(0))).await;
+        assert_eq!(result.unwrap(), Ok(()));
+        let result = server.send(request(ids[1], Request::Uci("e7e5".to_string())));
+        assert_eq!(result.await.unwrap(), Ok(()));
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use chess::{
+    chess::Board,
+    server::{self, ClientRequest, Request, RequestError},
+};
+use common::Sink;
+
+fn parse(fen: &str, uci: &str) -> Option<String> {
+    let board = Board::from_fen(fen).unwrap();
+    board.parse_uci(uci).map(|mv| mv.to_string())
+}
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const PROMOTION: &str = "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+const CASTLING: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+#[test]
+fn normal_move() {
+    assert_eq!(parse(START, "e2e4").as_deref(), Some("e2e4"));
+    assert_eq!(parse(START, "g1f3").as_deref(), Some("g1f3"));
+    assert_eq!(parse(START, "e2e5"), None);
+    assert_eq!(parse(START, "e7e5"), None);
+}
+
+#[test]
+fn promotion_piece() {
+    for (uci, expected) in [
+        ("a7a8q", "a7a8q"),
+        ("a7a8r", "a7a8r"),
+        ("a7a8b", "a7a8b"),
+        ("a7b8n", "a7b8n"),
+    ] {
+        assert_eq!(parse(PROMOTION, uci).as_deref(), Some(expected));
+    }
+    assert_eq!(parse(PROMOTION, "a7a8"), None);
+    assert_eq!(parse(PROMOTION, "a7a8k"), None);
+    assert_eq!(parse(PROMOTION, "a7a8p"), None);
+    assert_eq!(parse(PROMOTION, "a7a8Q"), None);
+    assert_eq!(parse(START, "e2e4q"), None);
+}
+
+#[test]
+fn castling_as_king_move() {
+    assert_eq!(parse(CASTLING, "e1g1").as_deref(), Some("e1g1"));
+    assert_eq!(parse(CASTLING, "e1c1").as_deref(), Some("e1c1"));
+    assert_eq!(parse(CASTLING, "e1h1"), None);
+}
+
+#[test]
+fn malformed() {
+    for uci in [
+        "", "e2", "e2e", "e2e44", "i2i4", "e9e4", "2e4e", "e2-e4", "e2e4qq",
+    ] {
+        assert_eq!(parse(START, uci), None, "{:?}", uci);
+    }
+}
+
+#[actix::test]
+async fn uci_request() {
+    let server = common::start(server::Config::default());
+    let white = common::connect(&server, &Sink::default()).await;
+    let black = common::connect(&server, &Sink::default()).await;
+    let request = |id, uci: &str| ClientRequest {
+        id,
+        request: Request::Uci(uci.to_string()),
+    };
+
+    let result = server.send(request(white, "e2e5")).await.unwrap();
+    assert_eq!(result, Err(RequestError::IllegalMove));
+    let result = server.send(request(white, "nonsense")).await.unwrap();
+    assert_eq!(result, Err(RequestError::IllegalMove));
+    let result = server.send(request(black, "e7e5")).await.unwrap();
+    assert_eq!(result, Err(RequestError::NotYourTurn));
+    let result = server.send(request(white, "e2e4")).await.unwrap();
+    assert_eq!(result, Ok(()));
+    let result = server.send(request(black, "e7e5")).await.unwrap();
+    assert_eq!(result, Ok(()));
+
+    let request: Request = serde_json::from_str(r#"{"Uci":"g1f3"}"#).unwrap();
+    let result = server
+        .send(ClientRequest { id: white, request })
+        .await
+        .unwrap();
+    assert_eq!(result, Ok(()));
+}